@@ -0,0 +1,50 @@
+use std::error::Error;
+use std::fmt;
+
+/// How to handle a key that an operation would bind in a context which already binds it
+/// differently.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ConflictPolicy {
+    /// Replaces the existing value.
+    #[default]
+    Overwrite,
+    /// Keeps the existing value.
+    Keep,
+    /// Rejects the whole operation, leaving everything unchanged.
+    Error
+}
+
+
+/// The keys that made an operation fail under [ConflictPolicy::Error].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ConflictError<K> {
+    /// The conflicting keys, in no particular order.
+    pub keys: Vec<K>
+}
+
+
+impl<K> fmt::Display for ConflictError<K>
+where K: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "conflicting keys: {:?}", self.keys)
+    }
+}
+
+
+impl<K> Error for ConflictError<K>
+where K: fmt::Debug {}
+
+
+/// A key changed differently by both sides of a
+/// [ContextManager::merge_fork](crate::ContextManager::merge_fork).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MergeConflict<K, V> {
+    /// The conflicting key.
+    pub key: K,
+    /// The value visible in the common ancestor, if any.
+    pub base: Option<V>,
+    /// The value visible in the manager being merged into, if any.
+    pub ours: Option<V>,
+    /// The value visible in the fork, if any.
+    pub theirs: Option<V>
+}
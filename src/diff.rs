@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The differences between two effective views of a
+/// [ContextManager](crate::ContextManager), as returned by
+/// [ContextManager::diff](crate::ContextManager::diff) and
+/// [ContextManager::diff_frames](crate::ContextManager::diff_frames).
+#[derive(Debug)]
+pub struct ContextDiff<'a, K, V> {
+    /// Keys only visible in the new view, with their values.
+    pub added: HashMap<&'a K, &'a V>,
+    /// Keys only visible in the old view, with their values.
+    pub removed: HashMap<&'a K, &'a V>,
+    /// Keys visible in both views with different values, with the old and the new value.
+    pub changed: HashMap<&'a K, (&'a V, &'a V)>
+}
+
+
+impl<K, V> Clone for ContextDiff<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            added: self.added.clone(),
+            removed: self.removed.clone(),
+            changed: self.changed.clone()
+        }
+    }
+}
+
+
+impl<K, V> PartialEq for ContextDiff<'_, K, V>
+where K: Hash + Eq, V: PartialEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.added == other.added && self.removed == other.removed && self.changed == other.changed
+    }
+}
+
+
+impl<K, V> Eq for ContextDiff<'_, K, V>
+where K: Hash + Eq, V: Eq {}
+
+
+impl<'a, K, V> ContextDiff<'a, K, V>
+where K: Hash + Eq, V: PartialEq {
+    /// Compares the `old` and `new` views.
+    pub(crate) fn between(old: HashMap<&'a K, &'a V>, mut new: HashMap<&'a K, &'a V>) -> Self {
+        let mut removed = HashMap::new();
+        let mut changed = HashMap::new();
+
+        for (key, old) in old {
+            match new.remove(key) {
+                None => { removed.insert(key, old); },
+                Some(new) if new != old => { changed.insert(key, (old, new)); },
+                Some(_) => {}
+            }
+        }
+
+        Self { added: new, removed, changed }
+    }
+
+    /// Whether both views are equal.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let fork = manager.fork().unwrap();
+    ///
+    /// assert!(ContextManager::diff(&manager, &fork).is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
@@ -0,0 +1,137 @@
+/// How a key resolves in a [ContextManager](crate::ContextManager), as returned by
+/// [ContextManager::explain](crate::ContextManager::explain).
+#[derive(Debug, PartialEq, Eq)]
+pub struct Lookup<'a, K, V, M = ()> {
+    /// The key as stored in the winning context, if any context holds it.
+    pub key: Option<&'a K>,
+    /// Every context searched, in search order.
+    pub searched: Vec<Layer<'a, V, M>>
+}
+
+
+/// A context searched by a lookup.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Layer<'a, V, M = ()> {
+    /// The index of the context, zero being the local context.
+    pub depth: usize,
+    /// The metadata of the context.
+    pub metadata: &'a M,
+    /// The value the context holds for the key, if any.
+    pub value: Option<&'a V>
+}
+
+
+impl<K, V, M> Clone for Lookup<'_, K, V, M> {
+    fn clone(&self) -> Self { Self { key: self.key, searched: self.searched.clone() } }
+}
+
+
+impl<V, M> Clone for Layer<'_, V, M> {
+    fn clone(&self) -> Self { *self }
+}
+
+
+impl<V, M> Copy for Layer<'_, V, M> {}
+
+
+impl<'a, K, V, M> Lookup<'a, K, V, M> {
+    /// Returns the index of the winning context.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(manager.explain("w").depth(), Some(1));
+    /// assert_eq!(manager.explain("x").depth(), None);
+    /// ```
+    pub fn depth(&self) -> Option<usize> { self.winner().map(|layer| layer.depth) }
+
+    /// Returns an iterator over the searched contexts that hold the key, winner first.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// manager.push_empty();
+    /// manager.push(HashMap::from([("w", 2)]));
+    ///
+    /// let depths: Vec<_> = manager.explain("w").holders().map(|layer| layer.depth).collect();
+    ///
+    /// assert_eq!(depths, vec![0, 2]);
+    /// ```
+    pub fn holders(&self) -> impl Iterator<Item = &Layer<'a, V, M>> {
+        self.searched.iter().filter(|layer| layer.value.is_some())
+    }
+
+    /// Returns the metadata of the winning context.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use std::hash::RandomState;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::<&str, i32, RandomState, &str>::default();
+    ///
+    /// manager.push_with_metadata(HashMap::from([("w", 1)]), "defaults.toml");
+    /// manager.push_with_metadata(HashMap::new(), "user.toml");
+    ///
+    /// assert_eq!(manager.explain("w").metadata(), Some(&"defaults.toml"));
+    /// ```
+    pub fn metadata(&self) -> Option<&'a M> { self.winner().map(|layer| layer.metadata) }
+
+    /// Returns an iterator over the values hidden by the winning value, ordered by precedence.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// manager.push(HashMap::from([("w", 2)]));
+    /// manager.push(HashMap::from([("w", 3)]));
+    ///
+    /// let shadowed: Vec<_> = manager.explain("w").shadowed().collect();
+    ///
+    /// assert_eq!(shadowed, vec![&2, &1]);
+    /// ```
+    pub fn shadowed(&self) -> impl Iterator<Item = &'a V> + '_ {
+        self.holders().skip(1).filter_map(|layer| layer.value)
+    }
+
+    /// Returns the winning value.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// manager.push(HashMap::from([("w", 2)]));
+    ///
+    /// assert_eq!(manager.explain("w").value(), Some(&2));
+    /// assert_eq!(manager.explain("x").value(), None);
+    /// ```
+    pub fn value(&self) -> Option<&'a V> { self.winner().and_then(|layer| layer.value) }
+
+    /// Returns the context the winning value was found in.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::{ContextManager, Layer};
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// manager.push_empty();
+    ///
+    /// let lookup = manager.explain("w");
+    ///
+    /// assert_eq!(lookup.winner(), Some(&Layer { depth: 1, metadata: &(), value: Some(&1) }));
+    /// ```
+    pub fn winner(&self) -> Option<&Layer<'a, V, M>> { self.holders().next() }
+}
@@ -0,0 +1,648 @@
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, RandomState};
+
+use crate::{Frame, ScopeId};
+
+/// The order in which the ancestors of a scope in a [ScopeGraph] are searched.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ResolutionOrder {
+    /// Searches each parent and all of its ancestors before moving on to the next parent.
+    #[default]
+    DepthFirst,
+    /// Searches all parents before any grandparents, and so on.
+    BreadthFirst,
+    /// Searches ancestors in their C3 linearization, which keeps every scope before its parents
+    /// and parents in their declared order.
+    C3
+}
+
+
+/// The reasons a parent link can be rejected by a [ScopeGraph].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GraphError {
+    /// Linking `scope` to `parent` would make `scope` its own ancestor.
+    Cycle { scope: ScopeId, parent: ScopeId },
+    /// The ancestors of `scope` have no C3 linearization.
+    Inconsistent { scope: ScopeId }
+}
+
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::Cycle { scope, parent } => write!(
+                f, "scope {} is an ancestor of scope {}", scope.index(), parent.index()
+            ),
+            GraphError::Inconsistent { scope } => write!(
+                f, "scope {} has no consistent resolution order", scope.index()
+            )
+        }
+    }
+}
+
+
+impl Error for GraphError {}
+
+
+/// An arena of scopes where each scope can inherit from several parents.
+///
+/// Resolving a key in a scope searches the scope and then its ancestors in the graph's
+/// [ResolutionOrder], stopping at the first scope holding the key. Parents declared first have
+/// higher precedence. Links that would create a cycle, or that would leave a scope without a
+/// C3 linearization when the graph resolves in that order, are rejected.
+///
+/// Scope identifiers are only meaningful for the graph that created them. Methods taking a
+/// [ScopeId] panic if the scope does not exist in the graph.
+#[derive(Clone, Debug)]
+pub struct ScopeGraph<K, V, S = RandomState, M = ()> {
+    scopes: Vec<Node<K, V, S, M>>,
+    order: ResolutionOrder
+}
+
+
+/// A scope, the links to its parents and children, and its resolution order.
+#[derive(Clone, Debug)]
+struct Node<K, V, S, M> {
+    frame: Frame<K, V, S, M>,
+    parents: Vec<ScopeId>,
+    children: Vec<ScopeId>,
+    linearization: Vec<ScopeId>
+}
+
+
+impl<K, V> ScopeGraph<K, V, RandomState>
+where K: Hash + Eq {
+    /// Creates an empty graph that resolves keys depth-first.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::new();
+    /// let scope = graph.add_scope(&[]).unwrap();
+    ///
+    /// graph.insert(scope, "x", 1);
+    ///
+    /// assert_eq!(graph.resolve(scope, "x"), Some(&1));
+    /// ```
+    pub fn new() -> Self { Self::default() }
+}
+
+
+impl<K, V, S, M> ScopeGraph<K, V, S, M> {
+    /// Returns a reference to the context of `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::new();
+    /// let scope = graph.add_scope(&[]).unwrap();
+    ///
+    /// graph.insert(scope, "x", 1);
+    ///
+    /// assert_eq!(graph.context(scope), &HashMap::from([("x", 1)]));
+    /// ```
+    pub fn context(&self, scope: ScopeId) -> &HashMap<K, V, S> { &self.scopes[scope.0].frame.map }
+
+    /// Returns a mutable reference to the context of `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::new();
+    /// let scope = graph.add_scope(&[]).unwrap();
+    ///
+    /// graph.context_mut(scope).insert("x", 1);
+    ///
+    /// assert_eq!(graph.resolve(scope, "x"), Some(&1));
+    /// ```
+    pub fn context_mut(&mut self, scope: ScopeId) -> &mut HashMap<K, V, S> {
+        &mut self.scopes[scope.0].frame.map
+    }
+
+    /// Whether the graph contains no scopes.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::<&str, i32>::new();
+    ///
+    /// assert!(graph.is_empty());
+    ///
+    /// graph.add_scope(&[]).unwrap();
+    ///
+    /// assert!(!graph.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool { self.scopes.is_empty() }
+
+    /// Returns the number of scopes in the graph.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::<&str, i32>::new();
+    /// let base = graph.add_scope(&[]).unwrap();
+    ///
+    /// graph.add_scope(&[base]).unwrap();
+    ///
+    /// assert_eq!(graph.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize { self.scopes.len() }
+
+    /// Returns the scopes searched when resolving a key in `scope`, in order, starting with
+    /// `scope` itself.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::{ResolutionOrder, ScopeGraph};
+    /// let mut graph = ScopeGraph::<&str, i32>::with_order(ResolutionOrder::C3);
+    /// let object = graph.add_scope(&[]).unwrap();
+    /// let left = graph.add_scope(&[object]).unwrap();
+    /// let right = graph.add_scope(&[object]).unwrap();
+    /// let child = graph.add_scope(&[left, right]).unwrap();
+    ///
+    /// assert_eq!(graph.linearization(child), &[child, left, right, object]);
+    /// ```
+    pub fn linearization(&self, scope: ScopeId) -> &[ScopeId] {
+        &self.scopes[scope.0].linearization
+    }
+
+    /// Returns a reference to the metadata of `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::hash::RandomState;
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::<&str, i32, RandomState, &str>::default();
+    /// let base = graph.add_scope_with_metadata(&[], "base").unwrap();
+    ///
+    /// assert_eq!(graph.metadata(base), &"base");
+    /// ```
+    pub fn metadata(&self, scope: ScopeId) -> &M { &self.scopes[scope.0].frame.meta }
+
+    /// Returns a mutable reference to the metadata of `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::hash::RandomState;
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::<&str, i32, RandomState, usize>::default();
+    /// let base = graph.add_scope(&[]).unwrap();
+    ///
+    /// *graph.metadata_mut(base) = 2;
+    ///
+    /// assert_eq!(graph.metadata(base), &2);
+    /// ```
+    pub fn metadata_mut(&mut self, scope: ScopeId) -> &mut M {
+        &mut self.scopes[scope.0].frame.meta
+    }
+
+    /// Returns the order in which the graph resolves keys.
+    pub fn order(&self) -> ResolutionOrder { self.order }
+
+    /// Returns the parents of `scope` in order of precedence.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::<&str, i32>::new();
+    /// let left = graph.add_scope(&[]).unwrap();
+    /// let right = graph.add_scope(&[]).unwrap();
+    /// let child = graph.add_scope(&[left, right]).unwrap();
+    ///
+    /// assert_eq!(graph.parents(child), &[left, right]);
+    /// assert!(graph.parents(left).is_empty());
+    /// ```
+    pub fn parents(&self, scope: ScopeId) -> &[ScopeId] { &self.scopes[scope.0].parents }
+
+    /// Creates an empty graph that resolves keys in `order`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::hash::RandomState;
+    /// # use contexts::{ResolutionOrder, ScopeGraph};
+    /// let graph = ScopeGraph::<&str, i32>::with_order(ResolutionOrder::C3);
+    ///
+    /// assert_eq!(graph.order(), ResolutionOrder::C3);
+    ///
+    /// let mut graph = ScopeGraph::<&str, i32, RandomState, &str>::with_order(
+    ///     ResolutionOrder::BreadthFirst
+    /// );
+    /// let base = graph.add_scope_with_metadata(&[], "base").unwrap();
+    ///
+    /// assert_eq!(graph.order(), ResolutionOrder::BreadthFirst);
+    /// assert_eq!(graph.metadata(base), &"base");
+    /// ```
+    pub fn with_order(order: ResolutionOrder) -> Self { Self { scopes: Vec::new(), order } }
+
+    /// Adds `parent` as the lowest precedence parent of `scope`.
+    ///
+    /// Returns an error and leaves the graph unchanged if `parent` is `scope` or one of its
+    /// descendants, or if the link would leave `scope` or one of its descendants without a
+    /// resolution order. Adding an existing parent again has no effect.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::{GraphError, ScopeGraph};
+    /// let mut graph = ScopeGraph::new();
+    /// let base = graph.add_scope(&[]).unwrap();
+    /// let child = graph.add_scope(&[]).unwrap();
+    ///
+    /// graph.insert(base, "x", 1);
+    ///
+    /// assert_eq!(graph.add_parent(child, base), Ok(()));
+    /// assert_eq!(graph.resolve(child, "x"), Some(&1));
+    /// assert_eq!(
+    ///     graph.add_parent(base, child),
+    ///     Err(GraphError::Cycle { scope: base, parent: child })
+    /// );
+    /// ```
+    pub fn add_parent(&mut self, scope: ScopeId, parent: ScopeId) -> Result<(), GraphError> {
+        assert!(parent.0 < self.scopes.len(), "scope not found");
+
+        if self.scopes[scope.0].parents.contains(&parent) {
+            return Ok(());
+        }
+
+        if self.is_ancestor(scope, parent) {
+            return Err(GraphError::Cycle { scope, parent });
+        }
+
+        self.scopes[scope.0].parents.push(parent);
+        self.scopes[parent.0].children.push(scope);
+
+        let affected = self.descendants(scope);
+        let previous: Vec<Vec<ScopeId>> = affected
+            .iter()
+            .map(|id| self.scopes[id.0].linearization.clone())
+            .collect();
+
+        for &id in &affected {
+            match self.linearize(id) {
+                Some(linearization) => self.scopes[id.0].linearization = linearization,
+                None => {
+                    for (id, linearization) in affected.iter().zip(previous) {
+                        self.scopes[id.0].linearization = linearization;
+                    }
+
+                    self.scopes[scope.0].parents.pop();
+                    self.scopes[parent.0].children.pop();
+
+                    return Err(GraphError::Inconsistent { scope: id });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds a new empty scope with the given metadata that inherits from `parents`, returning
+    /// its identifier.
+    ///
+    /// Returns an error and leaves the graph unchanged if the new scope would have no
+    /// resolution order.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::hash::RandomState;
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::<&str, i32, RandomState, &str>::default();
+    /// let base = graph.add_scope_with_metadata(&[], "base.toml").unwrap();
+    /// let site = graph.add_scope_with_metadata(&[base], "site.toml").unwrap();
+    ///
+    /// assert_eq!(graph.metadata(site), &"site.toml");
+    /// assert_eq!(graph.parents(site), &[base]);
+    /// ```
+    pub fn add_scope_with_metadata(&mut self, parents: &[ScopeId], metadata: M)
+        -> Result<ScopeId, GraphError>
+    where S: Default {
+        let scope = ScopeId(self.scopes.len());
+        let mut unique = Vec::with_capacity(parents.len());
+
+        for &parent in parents {
+            assert!(parent.0 < self.scopes.len(), "scope not found");
+
+            if !unique.contains(&parent) {
+                unique.push(parent);
+            }
+        }
+
+        self.scopes.push(Node {
+            frame: Frame::new(HashMap::default(), metadata),
+            parents: unique,
+            children: Vec::new(),
+            linearization: Vec::new()
+        });
+
+        match self.linearize(scope) {
+            Some(linearization) => {
+                for index in 0..self.scopes[scope.0].parents.len() {
+                    let parent = self.scopes[scope.0].parents[index];
+
+                    self.scopes[parent.0].children.push(scope);
+                }
+
+                self.scopes[scope.0].linearization = linearization;
+
+                Ok(scope)
+            }
+            None => {
+                self.scopes.pop();
+
+                Err(GraphError::Inconsistent { scope })
+            }
+        }
+    }
+
+    /// Returns `scope` followed by every scope inheriting from it, each after all of its
+    /// parents.
+    fn descendants(&self, scope: ScopeId) -> Vec<ScopeId> {
+        let mut reachable = HashSet::from([scope]);
+        let mut stack = vec![scope];
+
+        while let Some(id) = stack.pop() {
+            for &child in &self.scopes[id.0].children {
+                if reachable.insert(child) {
+                    stack.push(child);
+                }
+            }
+        }
+
+        let mut pending: HashMap<ScopeId, usize> = reachable
+            .iter()
+            .map(|&id| {
+                let count = self.scopes[id.0].parents.iter().filter(|p| reachable.contains(p));
+
+                (id, count.count())
+            })
+            .collect();
+
+        let mut ready = VecDeque::from([scope]);
+        let mut ordered = Vec::with_capacity(reachable.len());
+
+        while let Some(id) = ready.pop_front() {
+            ordered.push(id);
+
+            for &child in &self.scopes[id.0].children {
+                if let Some(count) = pending.get_mut(&child) {
+                    *count -= 1;
+
+                    if *count == 0 {
+                        ready.push_back(child);
+                    }
+                }
+            }
+        }
+
+        ordered
+    }
+
+    /// Whether `ancestor` is `scope` or can be reached from it through parent links.
+    fn is_ancestor(&self, ancestor: ScopeId, scope: ScopeId) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack = vec![scope];
+
+        while let Some(id) = stack.pop() {
+            if id == ancestor {
+                return true;
+            }
+
+            if visited.insert(id) {
+                stack.extend(self.scopes[id.0].parents.iter().copied());
+            }
+        }
+
+        false
+    }
+
+    /// Computes the resolution order of `scope` from its parents, or `None` if there is none.
+    ///
+    /// The linearizations of the parents of `scope` must be up to date.
+    fn linearize(&self, scope: ScopeId) -> Option<Vec<ScopeId>> {
+        let parents = &self.scopes[scope.0].parents;
+
+        match self.order {
+            ResolutionOrder::DepthFirst => {
+                let mut linearization = vec![scope];
+                let mut visited = HashSet::from([scope]);
+
+                for parent in parents {
+                    for &id in &self.scopes[parent.0].linearization {
+                        if visited.insert(id) {
+                            linearization.push(id);
+                        }
+                    }
+                }
+
+                Some(linearization)
+            }
+            ResolutionOrder::BreadthFirst => {
+                let mut linearization = vec![scope];
+                let mut visited = HashSet::from([scope]);
+                let mut queue: VecDeque<ScopeId> = parents.iter().copied().collect();
+
+                while let Some(id) = queue.pop_front() {
+                    if visited.insert(id) {
+                        linearization.push(id);
+                        queue.extend(self.scopes[id.0].parents.iter().copied());
+                    }
+                }
+
+                Some(linearization)
+            }
+            ResolutionOrder::C3 => {
+                let mut sequences: Vec<VecDeque<ScopeId>> = parents
+                    .iter()
+                    .map(|parent| self.scopes[parent.0].linearization.iter().copied().collect())
+                    .collect();
+
+                sequences.push(parents.iter().copied().collect());
+
+                let mut linearization = vec![scope];
+
+                loop {
+                    sequences.retain(|sequence| !sequence.is_empty());
+
+                    if sequences.is_empty() {
+                        return Some(linearization);
+                    }
+
+                    let head = sequences
+                        .iter()
+                        .map(|sequence| sequence[0])
+                        .find(|head| {
+                            sequences
+                                .iter()
+                                .all(|sequence| !sequence.range(1..).any(|id| id == head))
+                        })?;
+
+                    for sequence in &mut sequences {
+                        if sequence[0] == head {
+                            sequence.pop_front();
+                        }
+                    }
+
+                    linearization.push(head);
+                }
+            }
+        }
+    }
+}
+
+
+impl<K, V, S, M> ScopeGraph<K, V, S, M>
+where K: Hash + Eq, S: BuildHasher {
+    /// Adds a new empty scope that inherits from `parents`, returning its identifier.
+    ///
+    /// Parents declared first have higher precedence. Returns an error and leaves the graph
+    /// unchanged if the new scope would have no resolution order.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::{GraphError, ResolutionOrder, ScopeGraph};
+    /// let mut graph = ScopeGraph::<&str, i32>::with_order(ResolutionOrder::C3);
+    /// let x = graph.add_scope(&[]).unwrap();
+    /// let y = graph.add_scope(&[]).unwrap();
+    /// let a = graph.add_scope(&[x, y]).unwrap();
+    /// let b = graph.add_scope(&[y, x]).unwrap();
+    ///
+    /// assert!(matches!(graph.add_scope(&[a, b]), Err(GraphError::Inconsistent { .. })));
+    /// assert_eq!(graph.len(), 4);
+    /// ```
+    pub fn add_scope(&mut self, parents: &[ScopeId]) -> Result<ScopeId, GraphError>
+    where S: Default, M: Default {
+        self.add_scope_with_metadata(parents, M::default())
+    }
+
+    /// Whether a key is present in `scope` or any of its ancestors.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::new();
+    /// let base = graph.add_scope(&[]).unwrap();
+    /// let child = graph.add_scope(&[base]).unwrap();
+    ///
+    /// graph.insert(base, "x", 1);
+    ///
+    /// assert!(graph.contains_key(child, "x"));
+    /// assert!(!graph.contains_key(child, "y"));
+    /// ```
+    pub fn contains_key<Q>(&self, scope: ScopeId, key: &Q) -> bool
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.resolve_scope(scope, key).is_some()
+    }
+
+    /// Returns a reference to the value associated with `key` in `scope` alone.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::new();
+    /// let base = graph.add_scope(&[]).unwrap();
+    /// let child = graph.add_scope(&[base]).unwrap();
+    ///
+    /// graph.insert(base, "x", 1);
+    ///
+    /// assert_eq!(graph.get_local(base, "x"), Some(&1));
+    /// assert_eq!(graph.get_local(child, "x"), None);
+    /// ```
+    pub fn get_local<Q>(&self, scope: ScopeId, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.scopes[scope.0].frame.map.get(key)
+    }
+
+    /// Associates `value` with `key` in `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::new();
+    /// let scope = graph.add_scope(&[]).unwrap();
+    ///
+    /// assert_eq!(graph.insert(scope, "x", 1), None);
+    /// assert_eq!(graph.insert(scope, "x", 2), Some(1));
+    /// ```
+    pub fn insert(&mut self, scope: ScopeId, key: K, value: V) -> Option<V> {
+        self.scopes[scope.0].frame.map.insert(key, value)
+    }
+
+    /// Removes `key` from `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::new();
+    /// let scope = graph.add_scope(&[]).unwrap();
+    ///
+    /// graph.insert(scope, "x", 1);
+    ///
+    /// assert_eq!(graph.remove(scope, "x"), Some(1));
+    /// assert_eq!(graph.remove(scope, "x"), None);
+    /// ```
+    pub fn remove<Q>(&mut self, scope: ScopeId, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.scopes[scope.0].frame.map.remove(key)
+    }
+
+    /// Returns a reference to the value associated with `key`, searching `scope` and then its
+    /// ancestors in the graph's resolution order.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::{ResolutionOrder, ScopeGraph};
+    /// let orders = [(ResolutionOrder::DepthFirst, 1), (ResolutionOrder::BreadthFirst, 2)];
+    ///
+    /// for (order, expected) in orders {
+    ///     let mut graph = ScopeGraph::<&str, i32>::with_order(order);
+    ///     let root = graph.add_scope(&[]).unwrap();
+    ///     let left = graph.add_scope(&[root]).unwrap();
+    ///     let right = graph.add_scope(&[]).unwrap();
+    ///     let child = graph.add_scope(&[left, right]).unwrap();
+    ///
+    ///     graph.insert(root, "x", 1);
+    ///     graph.insert(right, "x", 2);
+    ///
+    ///     assert_eq!(graph.resolve(child, "x"), Some(&expected));
+    /// }
+    /// ```
+    pub fn resolve<Q>(&self, scope: ScopeId, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.scopes[scope.0]
+            .linearization
+            .iter()
+            .find_map(|id| self.scopes[id.0].frame.map.get(key))
+    }
+
+    /// Returns the scope that defines the value `key` resolves to from `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeGraph;
+    /// let mut graph = ScopeGraph::new();
+    /// let base = graph.add_scope(&[]).unwrap();
+    /// let child = graph.add_scope(&[base]).unwrap();
+    ///
+    /// graph.insert(base, "x", 1);
+    ///
+    /// assert_eq!(graph.resolve_scope(child, "x"), Some(base));
+    /// assert_eq!(graph.resolve_scope(child, "y"), None);
+    /// ```
+    pub fn resolve_scope<Q>(&self, scope: ScopeId, key: &Q) -> Option<ScopeId>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.scopes[scope.0]
+            .linearization
+            .iter()
+            .copied()
+            .find(|id| self.scopes[id.0].frame.map.contains_key(key))
+    }
+}
+
+
+impl<K, V, S, M> Default for ScopeGraph<K, V, S, M> {
+    /// Creates an empty `ScopeGraph<K, V, S, M>` that resolves keys depth-first.
+    fn default() -> Self { Self { scopes: Vec::new(), order: ResolutionOrder::default() } }
+}
@@ -11,13 +11,33 @@
 //! [ContextManager::new] or [ContextManager::with_capacity] do not start with an initial context.
 //! The [ContextManager::with_empty] and [ContextManager::from] methods create managers with one or
 //! more initial contexts. Inserts and removes have no effect until a first context is pushed.
+//! Other contexts can be read, written, replaced or removed by their index. The last context is
+//! considered the *global* context and has its own set of operations.
+//! Contexts pushed as *barriers* hide the contexts beneath them from lookups, which resume at
+//! the global contexts instead.
+//! Entries inserted with [ContextManager::insert_private] are private to their context and are only
+//! visible while it is the local one.
 //!
-//! Managers can be cloned from any point in the underlying collection.
+//! Managers can be cloned from any point in the underlying collection. Forks can be merged back
+//! into their origin with [ContextManager::merge_fork], or moved onto an updated origin with
+//! [ContextManager::rebase].
+//!
+//! Each context can carry a value of user metadata, such as a source path or a scope kind, that
+//! is pushed and popped along with it. The metadata type defaults to `()`.
 //!
 //! Context managers do not currently support direct iteration over key-value pairs, however
 //! any manager can be *collapsed* into a single `HashMap` or `BTreeMap` and iterated from
 //! there. Keys in these maps will have their most recently associated value from the manager.
 //!
+//! A [ContextView] borrows a range of a manager's contexts for read-only lookups. A [ChildScope]
+//! extends a manager with contexts of its own without cloning or mutably borrowing it, and a
+//! [ScopeTree] keeps every scope of a program queryable after it has been left. A [ScopeGraph] lets
+//! a scope inherit from several parents with a configurable resolution order.
+//!
+//! The bindings visible from two managers can be compared with [ContextManager::diff]. A
+//! [ContextPatch] records sets, updates and removals that can be applied to the local context or
+//! pushed as a new one, and applying it returns the patch that reverts it.
+//!
 //! ## Examples
 //!
 //! ```rust
@@ -120,14 +140,251 @@
 //! ```
 
 use std::borrow::Borrow;
-use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::hash::{BuildHasher, Hash, RandomState};
-use std::ops::Index;
+use std::ops::{Bound, Index, Range, RangeBounds};
+
+pub use conflict::{ConflictError, ConflictPolicy, MergeConflict};
+pub use diff::ContextDiff;
+pub use explain::{Layer, Lookup};
+pub use graph::{GraphError, ResolutionOrder, ScopeGraph};
+pub use patch::ContextPatch;
+pub use scope::ChildScope;
+pub use shadow::{InsertOutcome, ShadowPolicy};
+pub use suggest::LookupError;
+pub use tree::{ScopeId, ScopeTree};
+pub use view::ContextView;
+
+use reads::{FrameId, ReadCounts};
+
+mod conflict;
+mod diff;
+mod explain;
+mod graph;
+mod patch;
+mod reads;
+mod scope;
+mod shadow;
+mod suggest;
+mod tree;
+mod view;
 
 /// A singular view into a collection of `HashMap<K, V, S>`, each referred to as a context.
+///
+/// Every context carries a value of user metadata of type `M`, which defaults to `()`.
+#[derive(Debug)]
+pub struct ContextManager<K, V, S = RandomState, M = ()> {
+    inner: VecDeque<Frame<K, V, S, M>>,
+    global_depth: usize,
+    shadow_policy: ShadowPolicy,
+    access_tracking: bool,
+    reads: ReadCounts
+}
+
+
+/// A context together with its metadata.
 #[derive(Debug)]
-pub struct ContextManager<K, V, S = RandomState> {
-    inner: VecDeque<HashMap<K, V, S>>
+struct Frame<K, V, S, M> {
+    map: HashMap<K, V, S>,
+    meta: M,
+    barrier: bool,
+    inherited: bool,
+    private: Option<HashSet<K, S>>,
+    tombstones: Option<HashSet<K, S>>,
+    id: FrameId
+}
+
+
+impl<K, V, S, M> Frame<K, V, S, M> {
+    fn new(map: HashMap<K, V, S>, meta: M) -> Self {
+        Self {
+            map,
+            meta,
+            barrier: false,
+            inherited: false,
+            private: None,
+            tombstones: None,
+            id: FrameId::next()
+        }
+    }
+
+    /// Returns an iterator over the keys this context hides from the contexts beneath it.
+    fn buried(&self) -> impl Iterator<Item = &K> { self.tombstones.iter().flatten() }
+}
+
+
+impl<K, V, S, M> Frame<K, V, S, M>
+where K: Hash + Eq, S: BuildHasher {
+    /// Removes the entry for `key` and hides the key from the contexts beneath this one.
+    fn bury(&mut self, key: K)
+    where S: Clone {
+        self.remove(&key);
+
+        let hasher = self.map.hasher().clone();
+
+        self.tombstones.get_or_insert_with(|| HashSet::with_hasher(hasher)).insert(key);
+    }
+
+    /// Removes the private entries whose key satisfies `predicate`.
+    fn discard_private<F>(&mut self, mut predicate: F)
+    where F: FnMut(&K) -> bool {
+        if let Some(mut private) = self.private.take() {
+            private.retain(|key| {
+                let discarded = predicate(key);
+
+                if discarded {
+                    self.remove(key);
+                }
+
+                !discarded
+            });
+            self.private = Some(private);
+        }
+    }
+
+    /// Makes keys that are no longer present inheritable when inserted again.
+    fn drop_stale_private(&mut self) {
+        if let Some(private) = &mut self.private {
+            private.retain(|key| self.map.contains_key(key));
+        }
+    }
+
+    /// Whether `key` is hidden from the contexts beneath this one.
+    ///
+    /// A removed key bound again through the raw map is no longer hidden.
+    fn hides<Q>(&self, key: &Q) -> bool
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        !self.map.contains_key(key)
+            && self.tombstones.as_ref().is_some_and(|tombstones| tombstones.contains(key))
+    }
+
+    /// Associates `value` with `key`, making the entry inheritable.
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(private) = &mut self.private {
+            private.remove(&key);
+        }
+
+        if let Some(tombstones) = &mut self.tombstones {
+            tombstones.remove(&key);
+        }
+
+        self.map.insert(key, value)
+    }
+
+    /// Associates `value` with `key`, making the entry private.
+    fn insert_private(&mut self, key: K, value: V) -> Option<V>
+    where K: Clone, S: Clone {
+        if let Some(tombstones) = &mut self.tombstones {
+            tombstones.remove(&key);
+        }
+
+        self.private
+            .get_or_insert_with(|| HashSet::with_hasher(self.map.hasher().clone()))
+            .insert(key.clone());
+
+        self.map.insert(key, value)
+    }
+
+    /// Whether the entry for `key` is only visible while this context is local.
+    fn is_private<Q>(&self, key: &Q) -> bool
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.private.as_ref().is_some_and(|private| private.contains(key))
+    }
+
+    /// Returns the value associated with `key` if it is visible from the local context, given
+    /// whether this context is the local one.
+    fn lookup<Q>(&self, key: &Q, local: bool) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        if local || !self.is_private(key) { self.map.get(key) } else { None }
+    }
+
+    /// Returns the key-value pair for `key` if it is visible from the local context, given
+    /// whether this context is the local one.
+    fn lookup_key_value<Q>(&self, key: &Q, local: bool) -> Option<(&K, &V)>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        if local || !self.is_private(key) { self.map.get_key_value(key) } else { None }
+    }
+
+    /// Returns the value associated with `key` if it is visible from the local context, given
+    /// whether this context is the local one.
+    fn lookup_mut<Q>(&mut self, key: &Q, local: bool) -> Option<&mut V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        if local || !self.is_private(key) { self.map.get_mut(key) } else { None }
+    }
+
+    /// Returns the hash of `key` used to count reads of its entry.
+    fn key_hash<Q>(&self, key: &Q) -> u64
+    where Q: ?Sized + Hash {
+        self.map.hasher().hash_one(key)
+    }
+
+    /// Removes `key` along with its visibility and tombstone.
+    fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        if let Some(private) = &mut self.private {
+            private.remove(key);
+        }
+
+        if let Some(tombstones) = &mut self.tombstones {
+            tombstones.remove(key);
+        }
+
+        self.map.remove(key)
+    }
+}
+
+
+impl<K, V, S, M> Clone for Frame<K, V, S, M>
+where K: Clone, V: Clone, S: Clone, M: Clone {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+            meta: self.meta.clone(),
+            barrier: self.barrier,
+            inherited: self.inherited,
+            private: self.private.clone(),
+            tombstones: self.tombstones.clone(),
+            id: FrameId::next()
+        }
+    }
+}
+
+
+impl<K, V, S, M> From<HashMap<K, V, S>> for Frame<K, V, S, M>
+where M: Default {
+    fn from(map: HashMap<K, V, S>) -> Self { Self::new(map, M::default()) }
+}
+
+
+impl<K, V, S, M> PartialEq for Frame<K, V, S, M>
+where K: Hash + Eq, V: PartialEq, S: BuildHasher, M: PartialEq {
+    fn eq(&self, other: &Frame<K, V, S, M>) -> bool {
+        self.barrier == other.barrier
+            && self.meta == other.meta
+            && self.map == other.map
+            && self.map.keys().all(|key| self.is_private(key) == other.is_private(key))
+            && self.buried().chain(other.buried()).all(|key| self.hides(key) == other.hides(key))
+    }
+}
+
+
+/// Resolves `range` against a collection of `len` contexts, or returns `None` if it starts
+/// after it ends or ends after the last context.
+fn to_range<R>(range: R, len: usize) -> Option<Range<usize>>
+where R: RangeBounds<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1)?,
+        Bound::Unbounded => 0
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1)?,
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len
+    };
+
+    (start <= end && end <= len).then_some(start..end)
 }
 
 
@@ -144,7 +401,9 @@ where K: Hash + Eq {
     ///
     /// assert_eq!(&manager["x"], &1);
     /// ```
-    pub fn with_empty() -> Self { Self { inner: VecDeque::from([HashMap::new()]) } }
+    pub fn with_empty() -> Self {
+        Self::from_frames(VecDeque::from([Frame::from(HashMap::new())]))
+    }
 
     /// Creates an empty context manager.
     ///
@@ -171,11 +430,17 @@ where K: Hash + Eq {
     /// assert!(!manager.contains_key("x"));
     /// ```
     pub fn with_capacity(capacity: usize) -> Self {
-        Self { inner: VecDeque::with_capacity(capacity) }
+        Self::from_frames(VecDeque::with_capacity(capacity))
     }
+}
+
 
+impl<K, V, M> ContextManager<K, V, RandomState, M>
+where K: Hash + Eq {
     /// Aggregates all contexts into a single map where keys have their most recent value.
     ///
+    /// Private entries are only taken from the local context.
+    ///
     /// # Example
     /// ```
     /// # use std::collections::HashMap;
@@ -186,6 +451,9 @@ where K: Hash + Eq {
     /// manager.push(HashMap::from([("w", 1), ("x", 2)]));
     /// manager.push(HashMap::from([("y", 4), ("z", 3)]));
     ///
+    /// manager.insert_private("x", 5);
+    /// manager.push_empty();
+    ///
     /// let map = manager.collapse();
     ///
     /// assert_eq!(&map["w"], &1);
@@ -195,16 +463,24 @@ where K: Hash + Eq {
     /// ```
     pub fn collapse(mut self) -> HashMap<K, V> {
         if self.inner.len() == 1 {
-            self.inner.pop_front().unwrap()
+            self.inner.pop_front().unwrap().map
         } else {
             let mut map = HashMap::new();
 
             loop {
                 if self.inner.is_empty() { break; }
 
-                let next = self.inner.pop_back().unwrap();
+                let mut next = self.inner.pop_back().unwrap();
 
-                map.extend(next);
+                if !self.inner.is_empty() {
+                    next.discard_private(|_| true);
+                }
+
+                for key in next.buried() {
+                    map.remove(key);
+                }
+
+                map.extend(next.map);
             }
 
             map
@@ -227,150 +503,166 @@ where K: Hash + Eq {
     ///
     /// assert_eq!(manager.get_local("x"), None);
     /// ```
-    pub fn push_empty(&mut self) { self.inner.push_front(HashMap::new()) }
+    pub fn push_empty(&mut self)
+    where M: Default {
+        self.inner.push_front(Frame::from(HashMap::new()))
+    }
 }
 
 
-impl<K, V, S> ContextManager<K, V, S> {
-    /// Returns the number of contexts in the manager.
+impl<K, V, S> ContextManager<K, V, S>
+where K: Hash + Eq, S: BuildHasher + Clone {
+    /// Creates a context manager from a captured context, with an empty local context above it
+    /// so that new bindings do not alter the captured ones.
     ///
     /// # Example
     /// ```
     /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::<&str, i32>::new();
-    ///
-    /// assert!(manager.is_empty());
+    /// let mut manager = ContextManager::from([("w", 1), ("x", 2)]);
     ///
-    /// manager.push_empty();
+    /// manager.push(HashMap::from([("y", 3)]));
     ///
-    /// assert!(!manager.is_empty());
+    /// let (captured, _) = manager.capture(["x", "y"]);
+    /// let mut closure = ContextManager::from_capture(captured);
     ///
-    /// manager = ContextManager::with_empty();
+    /// closure.insert("x", 4);
     ///
-    /// assert!(!manager.is_empty());
+    /// assert_eq!(closure.len(), 2);
+    /// assert_eq!(closure.get("w"), None);
+    /// assert_eq!(&closure["x"], &4);
+    /// assert_eq!(closure.get_from(1, "x"), Some(&2));
+    /// assert_eq!(&closure["y"], &3);
     /// ```
-    pub fn is_empty(&self) -> bool { self.inner.is_empty() }
+    pub fn from_capture(context: HashMap<K, V, S>) -> Self {
+        let local = HashMap::with_hasher(context.hasher().clone());
 
-    /// Returns the number of contexts in the manager.
+        Self::from_frames(VecDeque::from([Frame::from(local), Frame::from(context)]))
+    }
+}
+
+
+impl<K, V, S, M> ContextManager<K, V, S, M> {
+    /// Moves all contexts of `other` beneath the contexts of this manager, leaving `other` empty.
     ///
     /// # Example
     /// ```
     /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::<&str, i32>::new();
-    ///
-    /// assert_eq!(manager.len(), 0);
-    ///
-    /// manager.push_empty();
-    ///
-    /// assert_eq!(manager.len(), 1);
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    /// let mut other = ContextManager::from([HashMap::from([("w", 2), ("x", 3)])]);
     ///
-    /// manager = ContextManager::with_empty();
+    /// manager.append(&mut other);
     ///
-    /// assert_eq!(manager.len(), 1);
+    /// assert!(other.is_empty());
+    /// assert_eq!(manager.len(), 2);
+    /// assert_eq!(manager.get_all("w"), vec![&1, &2]);
+    /// assert_eq!(&manager["x"], &3);
     /// ```
-    pub fn len(&self) -> usize { self.inner.len() }
+    pub fn append(&mut self, other: &mut ContextManager<K, V, S, M>) {
+        for frame in &mut other.inner {
+            frame.inherited = false;
+            self.reads.insert(frame.id, other.reads.take(frame.id));
+        }
 
-    /// Removes the local context if one is present.
+        self.inner.append(&mut other.inner)
+    }
+
+    /// Returns the number of contexts inherited from the manager this one was forked from,
+    /// which [ContextManager::rebase] replaces.
+    ///
+    /// Managers created through forks inherit every context they start with, while other
+    /// managers inherit none. Contexts stay inherited until they are removed, and contexts added
+    /// or moved in later are never inherited.
     ///
     /// # Example
     /// ```
     /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::with_empty();
-    ///
-    /// manager.insert("x", 1);
-    ///
-    /// let popped = manager.pop();
+    /// let mut manager = ContextManager::from([("w", 1)]);
     ///
-    /// assert!(popped.is_some());
-    /// assert_eq!(popped.unwrap().get("x"), Some(&1));
-    /// assert_eq!(manager.pop(), None);
-    /// ```
-    pub fn pop(&mut self) -> Option<HashMap<K, V, S>> { self.inner.pop_front() }
-
-    /// Adds a new local context.
+    /// manager.push(HashMap::from([("x", 2)]));
     ///
-    /// # Example
-    /// ```
-    /// # use std::collections::HashMap;
-    /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::with_empty();
+    /// let mut forked = manager.fork_from(1).unwrap();
     ///
-    /// manager.insert("x", 1);
+    /// forked.push_empty();
     ///
-    /// assert_eq!(&manager["x"], &1);
-    /// assert_eq!(manager.get("y"), None);
+    /// assert_eq!(manager.base_len(), 0);
+    /// assert_eq!(forked.base_len(), 2);
     ///
-    /// manager.push(HashMap::from([("y", 2)]));
+    /// forked.pop();
+    /// forked.pop();
+    /// forked.push_global(HashMap::new());
     ///
-    /// assert_eq!(&manager["y"], &2);
+    /// assert_eq!(forked.base_len(), 1);
     /// ```
-    pub fn push(&mut self, context: HashMap<K, V, S>) { self.inner.push_front(context) ; }
-}
-
+    pub fn base_len(&self) -> usize { self.inner.iter().filter(|frame| frame.inherited).count() }
 
-impl<K, V, S> ContextManager<K, V, S>
-where K: Hash + Eq, S: BuildHasher {
-    /// Aggregates all contexts storing each key and its most recent value into `src`.
+    /// Returns a reference to the context at `index`.
     ///
     /// # Example
     /// ```
     /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::with_capacity(3);
-    ///
-    /// manager.push(HashMap::from([("y", 4)]));
-    /// manager.push(HashMap::from([("w", 2), ("x", 3)]));
-    ///
-    /// let mut map = HashMap::from([("v", 1), ("x", 2), ("z", 5)]);
+    /// let mut manager = ContextManager::new();
     ///
-    /// manager.collapse_into(&mut map);
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("x", 2)]));
     ///
-    /// assert_eq!(&map["v"], &1);
-    /// assert_eq!(&map["w"], &2);
-    /// assert_eq!(&map["x"], &3);
-    /// assert_eq!(&map["y"], &4);
-    /// assert_eq!(&map["z"], &5);
+    /// assert_eq!(manager.context(0), Some(&HashMap::from([("x", 2)])));
+    /// assert_eq!(manager.context(1), Some(&HashMap::from([("w", 1)])));
+    /// assert_eq!(manager.context(2), None);
     /// ```
-    pub fn collapse_into(mut self, src: &mut HashMap<K, V, S>) {
-        loop {
-            if self.inner.is_empty() { break; }
+    pub fn context(&self, index: usize) -> Option<&HashMap<K, V, S>> {
+        self.inner.get(index).map(|frame| &frame.map)
+    }
 
-            let next = self.inner.pop_back().unwrap();
+    /// Creates a manager holding `inner`, all of which it inherits, with the settings of this
+    /// manager.
+    fn forked(&self, mut inner: VecDeque<Frame<K, V, S, M>>) -> Self {
+        for frame in &mut inner {
+            frame.inherited = true;
+        }
 
-            src.extend(next);
+        self.with_frames(inner)
+    }
+
+    /// Creates a manager holding `inner` with the default settings.
+    fn from_frames(inner: VecDeque<Frame<K, V, S, M>>) -> Self {
+        Self {
+            inner,
+            global_depth: 1,
+            shadow_policy: ShadowPolicy::Allow,
+            access_tracking: false,
+            reads: ReadCounts::default()
         }
     }
 
-    /// Whether a key is present in the context.
+    /// Returns the number of outermost contexts that lookups resume at after passing a barrier.
+    ///
+    /// The global depth of a new manager is one, and managers derived from this one through
+    /// forks or splits share its global depth.
     ///
     /// # Example
     /// ```
-    /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::with_capacity(3);
+    /// let mut manager = ContextManager::<&str, i32>::new();
     ///
-    /// manager.push(HashMap::from([("w", 1)]));
-    /// manager.push(HashMap::from([("x", 2)]));
+    /// assert_eq!(manager.global_depth(), 1);
     ///
-    /// assert!(manager.contains_key(&"w"));
-    /// assert!(manager.contains_key(&"x"));
-    /// assert!(!manager.contains_key(&"y"));
+    /// manager.set_global_depth(2);
+    ///
+    /// assert_eq!(manager.global_depth(), 2);
     /// ```
-    pub fn contains_key<Q>(&self, key: &Q) -> bool
-    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
-        for map in &self.inner {
-            if map.contains_key(key) {
-                return true;
-            }
-        }
-
-        false
-    }
+    pub fn global_depth(&self) -> usize { self.global_depth }
 
-    /// Whether a key is present in the local context
+    /// Inserts `context` at `index`, shifting all contexts from `index` onward outward.
+    ///
+    /// An `index` equal to the number of contexts adds `context` as the outermost context.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of contexts.
     ///
     /// # Example
     /// ```
@@ -379,124 +671,240 @@ where K: Hash + Eq, S: BuildHasher {
     /// let mut manager = ContextManager::new();
     ///
     /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 3)]));
     ///
-    /// assert!(manager.contains_local_key("w"));
-    ///
-    /// manager.push_empty();
+    /// manager.insert_context(1, HashMap::from([("w", 2)]));
     ///
-    /// assert!(!manager.contains_local_key("w"));
+    /// assert_eq!(manager.get_all("w"), vec![&3, &2, &1]);
     /// ```
-    pub fn contains_local_key<Q>(&self, key: &Q) -> bool
-    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
-        self.inner.len() > 0 && self.inner[0].contains_key(key)
+    pub fn insert_context(&mut self, index: usize, context: HashMap<K, V, S>)
+    where M: Default {
+        self.inner.insert(index, Frame::from(context))
     }
 
-    /// Returns a reference to the value associated with `key`.
+    /// Whether the context at `index` is a barrier.
     ///
     /// # Example
     /// ```
     /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::with_capacity(3);
+    /// let mut manager = ContextManager::from([("w", 1)]);
     ///
-    /// manager.push(HashMap::from([("w", 1)]));
-    /// manager.push(HashMap::from([("x", 2)]));
+    /// manager.push_barrier(HashMap::new());
     ///
-    /// assert_eq!(manager.get(&"w"), Some(&1));
-    /// assert_eq!(manager.get(&"x"), Some(&2));
-    /// assert_eq!(manager.get(&"y"), None);
+    /// assert!(manager.is_barrier(0));
+    /// assert!(!manager.is_barrier(1));
+    /// assert!(!manager.is_barrier(2));
     /// ```
-    pub fn get<Q>(&self, key: &Q) -> Option<&V>
-    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
-        self.inner.iter().find_map(|ctx| ctx.get(key))
+    pub fn is_barrier(&self, index: usize) -> bool {
+        self.inner.get(index).is_some_and(|frame| frame.barrier)
     }
 
-    /// Returns a vector of references to all values associated with `key`, ordered by
-    /// precedence.
+    /// Returns the number of contexts in the manager.
     ///
     /// # Example
     /// ```
     /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::with_capacity(3);
+    /// let mut manager = ContextManager::<&str, i32>::new();
     ///
-    /// manager.push(HashMap::from([("w", 1)]));
-    /// manager.push(HashMap::from([("w", 2)]));
-    /// manager.push(HashMap::from([("w", 3)]));
+    /// assert!(manager.is_empty());
     ///
-    /// assert_eq!(manager.get_all(&"w"), vec![&3, &2, &1]);
-    /// assert_eq!(manager.get_all(&"x"), Vec::<&i32>::new());
+    /// manager.push_empty();
+    ///
+    /// assert!(!manager.is_empty());
+    ///
+    /// manager = ContextManager::with_empty();
+    ///
+    /// assert!(!manager.is_empty());
     /// ```
-    pub fn get_all<Q>(&self, key: &Q) -> Vec<&V>
-    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
-        self.inner.iter().filter_map(|map| map.get(key)).collect()
-    }
+    pub fn is_empty(&self) -> bool { self.inner.is_empty() }
 
-    /// Returns a reference to the value associated with `key` starting with the context at `index`.
+    /// Whether reads through [ContextManager::get], [ContextManager::get_mut],
+    /// [ContextManager::contains_key] and indexing are counted.
     ///
     /// # Example
     /// ```
-    /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::with_capacity(3);
+    /// let mut manager = ContextManager::<&str, i32>::new();
     ///
-    /// manager.push(HashMap::from([("w", 1)]));
-    /// manager.push(HashMap::from([("x", 2)]));
-    /// manager.push(HashMap::from([("w", 3)]));
+    /// assert!(!manager.is_tracking_accesses());
     ///
-    /// assert_eq!(manager.get_from(0, &"w"), Some(&3));
-    /// assert_eq!(manager.get_from(1, &"w"), Some(&1));
-    /// assert_eq!(manager.get_from(2, &"w"), Some(&1));
-    /// assert_eq!(manager.get_from(3, &"w"), None);
+    /// manager.set_access_tracking(true);
+    ///
+    /// assert!(manager.is_tracking_accesses());
     /// ```
-    pub fn get_from<Q>(&self, index: usize, key: &Q) -> Option<&V>
-    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
-        self.inner.range(index..).find_map(|ctx| ctx.get(key))
-    }
+    pub fn is_tracking_accesses(&self) -> bool { self.access_tracking }
 
-    /// Returns a reference to the value associated with `key` in the local context.
+    /// Returns the number of contexts in the manager.
     ///
     /// # Example
-    ///```
+    /// ```
     /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::from([("w", 1)]);
+    /// let mut manager = ContextManager::<&str, i32>::new();
     ///
-    /// assert_eq!(manager.get_local(&"w"), Some(&1));
+    /// assert_eq!(manager.len(), 0);
     ///
     /// manager.push_empty();
     ///
-    /// assert_eq!(manager.get_local(&"w"), None);
+    /// assert_eq!(manager.len(), 1);
+    ///
+    /// manager = ContextManager::with_empty();
+    ///
+    /// assert_eq!(manager.len(), 1);
     /// ```
-    pub fn get_local<Q>(&self, key: &Q) -> Option<&V>
-    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
-        if self.inner.is_empty() { None } else { self.inner[0].get(key) }
+    pub fn len(&self) -> usize { self.inner.len() }
+
+    /// Returns a reference to the metadata of the context at `index`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use std::hash::RandomState;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::<&str, i32, RandomState, &str>::default();
+    ///
+    /// manager.push_with_metadata(HashMap::from([("w", 1)]), "global");
+    /// manager.push(HashMap::from([("w", 2)]));
+    ///
+    /// assert_eq!(manager.metadata(0), Some(&""));
+    /// assert_eq!(manager.metadata(1), Some(&"global"));
+    /// assert_eq!(manager.metadata(2), None);
+    /// ```
+    pub fn metadata(&self, index: usize) -> Option<&M> {
+        self.inner.get(index).map(|frame| &frame.meta)
     }
 
-    /// Returns a mutable reference to the value associated with `key` in the local context.
+    /// Returns a mutable reference to the metadata of the context at `index`.
     ///
     /// # Example
-    ///```
+    /// ```
     /// # use std::collections::HashMap;
+    /// # use std::hash::RandomState;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::from([("w", 1)]);
+    /// let mut manager = ContextManager::<&str, i32, RandomState, usize>::default();
     ///
-    /// if let Some(w) = manager.get_local_mut("w") {
-    ///     *w = 2;
+    /// manager.push_with_metadata(HashMap::from([("w", 1)]), 1);
+    ///
+    /// if let Some(line) = manager.metadata_mut(0) {
+    ///     *line = 2;
     /// }
     ///
-    /// assert_eq!(manager.get(&"w"), Some(&2));
+    /// assert_eq!(manager.metadata(0), Some(&2));
+    /// assert_eq!(manager.metadata_mut(1), None);
+    /// ```
+    pub fn metadata_mut(&mut self, index: usize) -> Option<&mut M> {
+        self.inner.get_mut(index).map(|frame| &mut frame.meta)
+    }
+
+    /// Removes the local context if one is present.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::with_empty();
+    ///
+    /// manager.insert("x", 1);
+    ///
+    /// let popped = manager.pop();
+    ///
+    /// assert!(popped.is_some());
+    /// assert_eq!(popped.unwrap().get("x"), Some(&1));
+    /// assert_eq!(manager.pop(), None);
+    /// ```
+    pub fn pop(&mut self) -> Option<HashMap<K, V, S>> { self.inner.pop_front().map(|frame| frame.map) }
+
+    /// Removes the global context, the one with the lowest precedence, if one is present.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    ///
+    /// assert_eq!(manager.pop_global(), Some(HashMap::from([("w", 1)])));
+    /// assert_eq!(manager.len(), 1);
+    /// assert_eq!(&manager["w"], &2);
+    /// ```
+    pub fn pop_global(&mut self) -> Option<HashMap<K, V, S>> {
+        self.inner.pop_back().map(|frame| frame.map)
+    }
+
+    /// Removes the local context and its metadata if one is present.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use std::hash::RandomState;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::<&str, i32, RandomState, &str>::default();
+    ///
+    /// manager.push_with_metadata(HashMap::from([("w", 1)]), "block");
+    ///
+    /// let (context, metadata) = manager.pop_with_metadata().unwrap();
+    ///
+    /// assert_eq!(context.get("w"), Some(&1));
+    /// assert_eq!(metadata, "block");
+    /// assert_eq!(manager.pop_with_metadata(), None);
+    /// ```
+    pub fn pop_with_metadata(&mut self) -> Option<(HashMap<K, V, S>, M)> {
+        self.inner.pop_front().map(|frame| (frame.map, frame.meta))
+    }
+
+    /// Adds a new local context.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::with_empty();
+    ///
+    /// manager.insert("x", 1);
+    ///
+    /// assert_eq!(&manager["x"], &1);
+    /// assert_eq!(manager.get("y"), None);
+    ///
+    /// manager.push(HashMap::from([("y", 2)]));
+    ///
+    /// assert_eq!(&manager["y"], &2);
+    /// ```
+    pub fn push(&mut self, context: HashMap<K, V, S>)
+    where M: Default {
+        self.inner.push_front(Frame::from(context))
+    }
+
+    /// Adds a new local context that is a barrier.
+    ///
+    /// Lookups that reach a barrier search it and then skip ahead to the global contexts, so
+    /// bindings between the barrier and the global contexts are hidden. Barriers within the
+    /// global contexts have no effect.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("print", 0)]);
     ///
+    /// manager.push(HashMap::from([("caller", 1)]));
+    /// manager.push_barrier(HashMap::from([("arg", 2)]));
     /// manager.push_empty();
     ///
-    /// assert_eq!(manager.get_local_mut(&"w"), None);
+    /// assert_eq!(manager.get("arg"), Some(&2));
+    /// assert_eq!(manager.get("caller"), None);
+    /// assert_eq!(manager.get("print"), Some(&0));
+    /// assert_eq!(manager.get_ignoring_barriers("caller"), Some(&1));
     /// ```
-    pub fn get_local_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
-    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
-        if self.inner.is_empty() { None } else { self.inner[0].get_mut(key) }
+    pub fn push_barrier(&mut self, context: HashMap<K, V, S>)
+    where M: Default {
+        self.inner.push_front(Frame { barrier: true, ..Frame::from(context) })
     }
 
-    /// Returns a mutable reference to the value associated with `key`.
+    /// Adds a new global context with lower precedence than every existing context.
     ///
     /// # Example
     /// ```
@@ -504,67 +912,1718 @@ where K: Hash + Eq, S: BuildHasher {
     /// # use contexts::ContextManager;
     /// let mut manager = ContextManager::from([("w", 1)]);
     ///
-    /// if let Some(v) = manager.get_mut("w") {
-    ///     *v = 2;
-    /// }
+    /// manager.push_global(HashMap::from([("w", 2), ("x", 3)]));
     ///
-    /// assert_eq!(&manager["w"], &2);
-    /// assert_eq!(manager.get_mut(&"x"), None);
+    /// assert_eq!(&manager["w"], &1);
+    /// assert_eq!(&manager["x"], &3);
+    /// assert_eq!(manager.len(), 2);
     /// ```
-    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
-    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
-        self.inner.iter_mut().find_map(|ctx| ctx.get_mut(key))
+    pub fn push_global(&mut self, context: HashMap<K, V, S>)
+    where M: Default {
+        self.inner.push_back(Frame::from(context))
+    }
+
+    /// Adds a new local context with the given metadata.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use std::hash::RandomState;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::<&str, i32, RandomState, &str>::default();
+    ///
+    /// manager.push_with_metadata(HashMap::from([("w", 1)]), "config.toml");
+    ///
+    /// assert_eq!(&manager["w"], &1);
+    /// assert_eq!(manager.metadata(0), Some(&"config.toml"));
+    /// ```
+    pub fn push_with_metadata(&mut self, context: HashMap<K, V, S>, metadata: M) {
+        self.inner.push_front(Frame::new(context, metadata))
+    }
+
+    /// Removes and returns the context at `index`, if there is one.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    /// manager.push(HashMap::from([("w", 3)]));
+    ///
+    /// assert_eq!(manager.remove_context(1), Some(HashMap::from([("w", 2)])));
+    /// assert_eq!(manager.get_all("w"), vec![&3, &1]);
+    /// assert_eq!(manager.remove_context(2), None);
+    /// ```
+    pub fn remove_context(&mut self, index: usize) -> Option<HashMap<K, V, S>> {
+        self.inner.remove(index).map(|frame| frame.map)
+    }
+
+    /// Replaces the context at `index` with `context`, returning the previous one.
+    ///
+    /// The metadata of the context at `index` is kept, while the visibility, read counts and
+    /// removals of its entries are reset. If there is no context at `index`, the manager is left
+    /// unchanged and `None` is returned.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("x", 2)]));
+    ///
+    /// let previous = manager.replace_context(1, HashMap::from([("w", 3)]));
+    ///
+    /// assert_eq!(previous, Some(HashMap::from([("w", 1)])));
+    /// assert_eq!(&manager["w"], &3);
+    /// assert_eq!(manager.replace_context(2, HashMap::new()), None);
+    /// ```
+    pub fn replace_context(&mut self, index: usize, context: HashMap<K, V, S>)
+        -> Option<HashMap<K, V, S>> {
+        self.inner.get_mut(index).map(|frame| {
+            frame.private = None;
+            frame.tombstones = None;
+            frame.id = FrameId::next();
+
+            std::mem::replace(&mut frame.map, context)
+        })
+    }
+
+    /// Returns the end of the contexts searched from `index` before a barrier, and the index the
+    /// search resumes at.
+    fn search_bounds(&self, index: usize) -> (usize, usize) {
+        let len = self.inner.len();
+        let globals = len.saturating_sub(self.global_depth);
+
+        match self.inner.range(index..).position(|frame| frame.barrier) {
+            Some(offset) if index + offset < globals => (index + offset + 1, globals),
+            _ => (len, len)
+        }
+    }
+
+    /// Enables or disables counting reads of each entry.
+    ///
+    /// Reads through [ContextManager::get], [ContextManager::get_mut],
+    /// [ContextManager::contains_key] and indexing are counted against the entry they resolve
+    /// to. Disabling tracking keeps the counts recorded so far, and clones of the manager keep
+    /// them too. Entries are told apart by the hash of their key, so keys of one context whose
+    /// hashes collide share a count.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// manager.get("w");
+    /// manager.set_access_tracking(true);
+    /// manager.get("w");
+    ///
+    /// assert_eq!(manager.access_count("w"), Some(1));
+    /// assert_eq!(manager.clone().access_count("w"), Some(1));
+    /// ```
+    pub fn set_access_tracking(&mut self, enabled: bool) { self.access_tracking = enabled }
+
+    /// Sets the number of outermost contexts that lookups resume at after passing a barrier.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("builtin", 0)]);
+    ///
+    /// manager.push(HashMap::from([("module", 1)]));
+    /// manager.push_barrier(HashMap::new());
+    ///
+    /// assert_eq!(manager.get("module"), None);
+    ///
+    /// manager.set_global_depth(2);
+    ///
+    /// assert_eq!(manager.get("module"), Some(&1));
+    /// assert_eq!(manager.get("builtin"), Some(&0));
+    /// ```
+    pub fn set_global_depth(&mut self, depth: usize) { self.global_depth = depth }
+
+    /// Sets whether [ContextManager::insert_checked] may shadow bindings in outer contexts.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::{ContextManager, ShadowPolicy};
+    /// let mut manager = ContextManager::<&str, i32>::new();
+    ///
+    /// manager.set_shadow_policy(ShadowPolicy::Reject);
+    ///
+    /// assert_eq!(manager.shadow_policy(), ShadowPolicy::Reject);
+    /// ```
+    pub fn set_shadow_policy(&mut self, policy: ShadowPolicy) { self.shadow_policy = policy }
+
+    /// Returns whether [ContextManager::insert_checked] may shadow bindings in outer contexts.
+    ///
+    /// New managers allow shadowing, and managers derived from this one through forks or
+    /// splits share its policy.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::{ContextManager, ShadowPolicy};
+    /// let manager = ContextManager::<&str, i32>::new();
+    ///
+    /// assert_eq!(manager.shadow_policy(), ShadowPolicy::Allow);
+    /// ```
+    pub fn shadow_policy(&self) -> ShadowPolicy { self.shadow_policy }
+
+    /// Moves the contexts from `index` onward into a new manager, leaving the contexts before
+    /// `index` in this one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of contexts.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    /// manager.push(HashMap::from([("w", 3)]));
+    ///
+    /// let outer = manager.split_off(1);
+    ///
+    /// assert_eq!(manager.get_all("w"), vec![&3]);
+    /// assert_eq!(outer.get_all("w"), vec![&2, &1]);
+    /// ```
+    pub fn split_off(&mut self, index: usize) -> ContextManager<K, V, S, M> {
+        let mut outer = self.inner.split_off(index);
+        let mut reads = ReadCounts::default();
+
+        for frame in &mut outer {
+            frame.inherited = false;
+            reads.insert(frame.id, self.reads.take(frame.id));
+        }
+
+        Self { reads, ..self.with_frames(outer) }
+    }
+
+    /// Keeps the `depth` most local contexts, dropping the rest.
+    ///
+    /// Has no effect if `depth` is not less than the number of contexts.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    /// manager.push(HashMap::from([("w", 3)]));
+    ///
+    /// manager.truncate(2);
+    ///
+    /// assert_eq!(manager.get_all("w"), vec![&3, &2]);
+    ///
+    /// manager.truncate(5);
+    ///
+    /// assert_eq!(manager.len(), 2);
+    /// ```
+    pub fn truncate(&mut self, depth: usize) { self.inner.truncate(depth) }
+
+    /// Drops the `n` outermost contexts, keeping the rest.
+    ///
+    /// Removes every context if `n` is not less than the number of contexts.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    /// manager.push(HashMap::from([("w", 3)]));
+    ///
+    /// manager.truncate_outer(1);
+    ///
+    /// assert_eq!(manager.get_all("w"), vec![&3, &2]);
+    ///
+    /// manager.truncate_outer(5);
+    ///
+    /// assert!(manager.is_empty());
+    /// ```
+    pub fn truncate_outer(&mut self, n: usize) {
+        self.inner.truncate(self.inner.len().saturating_sub(n))
+    }
+
+    /// Returns a read-only view into the contexts in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` starts after it ends or ends after the last context.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("x", 2)]));
+    /// manager.push(HashMap::from([("y", 3)]));
+    ///
+    /// let view = manager.view(..2);
+    ///
+    /// assert_eq!(view.len(), 2);
+    /// assert_eq!(view.get("x"), Some(&2));
+    /// assert_eq!(view.get("w"), None);
+    /// ```
+    pub fn view<R>(&self, range: R) -> ContextView<'_, K, V, S, M>
+    where R: RangeBounds<usize> {
+        let Range { start, end } = to_range(range, self.inner.len()).expect("range out of bounds");
+
+        let (front, back) = self.inner.as_slices();
+        let split = front.len();
+
+        if end <= split {
+            ContextView::new(&front[start..end], &[], start == 0)
+        } else if start >= split {
+            ContextView::new(&[], &back[(start - split)..(end - split)], start == 0)
+        } else {
+            ContextView::new(&front[start..], &back[..(end - split)], start == 0)
+        }
+    }
+
+    /// Returns the contexts searched by lookups starting at `index` along with their indices,
+    /// ordered by precedence, or none if `index` is past the outermost context.
+    fn visible(&self, index: usize) -> impl Iterator<Item = (usize, &Frame<K, V, S, M>)> {
+        let index = index.min(self.inner.len());
+        let (end, resume) = self.search_bounds(index);
+
+        (index..end)
+            .zip(self.inner.range(index..end))
+            .chain((resume..self.inner.len()).zip(self.inner.range(resume..)))
+    }
+
+    /// Returns the contexts searched by lookups starting at `index` along with their indices,
+    /// ordered by precedence, or none if `index` is past the outermost context.
+    fn visible_mut(&mut self, index: usize)
+        -> impl Iterator<Item = (usize, &mut Frame<K, V, S, M>)> {
+        let index = index.min(self.inner.len());
+        let (end, resume) = self.search_bounds(index);
+
+        (index..)
+            .zip(self.inner.range_mut(index..))
+            .filter(move |(index, _)| *index < end || *index >= resume)
+    }
+
+    /// Creates a manager holding `inner` with the settings of this manager.
+    fn with_frames(&self, inner: VecDeque<Frame<K, V, S, M>>) -> Self {
+        Self {
+            inner,
+            global_depth: self.global_depth,
+            shadow_policy: self.shadow_policy,
+            access_tracking: self.access_tracking,
+            reads: ReadCounts::default()
+        }
+    }
+}
+
+
+impl<K, V, S, M> ContextManager<K, V, S, M>
+where K: Hash + Eq, S: BuildHasher {
+    /// Returns the binding of `key` visible from the local context, counting the read if access
+    /// tracking is enabled.
+    fn access<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        let (frame, key, value) = self.search(0, key).find_map(|(index, ctx)| {
+            ctx.lookup_key_value(key, index == 0).map(|(key, value)| (ctx, key, value))
+        })?;
+
+        if self.access_tracking {
+            let live = self.inner.iter().map(|frame| frame.id);
+
+            self.reads.record(frame.id, frame.key_hash(key), live);
+        }
+
+        Some((key, value))
+    }
+
+    /// Returns the number of counted reads of the binding of `key` visible from the local
+    /// context, without counting this one.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// manager.set_access_tracking(true);
+    /// manager.push(HashMap::from([("w", 2)]));
+    ///
+    /// assert!(manager.contains_key("w"));
+    /// assert_eq!(&manager["w"], &2);
+    /// assert_eq!(manager.access_count("w"), Some(2));
+    ///
+    /// manager.pop();
+    ///
+    /// assert_eq!(manager.access_count("w"), Some(0));
+    /// assert_eq!(manager.access_count("x"), None);
+    /// ```
+    pub fn access_count<Q>(&self, key: &Q) -> Option<usize>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.search(0, key)
+            .find(|(index, ctx)| ctx.lookup(key, *index == 0).is_some())
+            .map(|(_, ctx)| self.reads.count(ctx.id, ctx.key_hash(key)))
+    }
+
+    /// Returns every binding passed by lookups starting at `index` along with the index of its
+    /// context, ordered by precedence.
+    fn bindings(&self, index: usize) -> Vec<(usize, &K, &V)> {
+        let mut buried = HashSet::new();
+        let mut bindings = Vec::new();
+
+        for (depth, frame) in self.visible(index) {
+            bindings.extend(frame.map
+                .iter()
+                .filter(|(key, _)| (depth == 0 || !frame.is_private(*key)) && !buried.contains(key))
+                .map(|(key, value)| (depth, key, value)));
+
+            buried.extend(frame.buried());
+        }
+
+        bindings
+    }
+
+    /// Creates a child scope with an empty local context that falls back to this manager for
+    /// lookups.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let mut scope = manager.child();
+    ///
+    /// scope.insert("x", 2);
+    ///
+    /// assert_eq!(&scope["w"], &1);
+    /// assert_eq!(&scope["x"], &2);
+    /// assert_eq!(manager.get("x"), None);
+    /// ```
+    pub fn child(&self) -> ChildScope<'_, K, V, S, M>
+    where S: Default, M: Default {
+        ChildScope::with_manager(self)
+    }
+
+    /// Aggregates all contexts storing each key and its most recent value into `src`.
+    ///
+    /// Private entries are only taken from the local context.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::with_capacity(3);
+    ///
+    /// manager.push(HashMap::from([("y", 4)]));
+    /// manager.push(HashMap::from([("w", 2), ("x", 3)]));
+    ///
+    /// let mut map = HashMap::from([("v", 1), ("x", 2), ("z", 5)]);
+    ///
+    /// manager.collapse_into(&mut map);
+    ///
+    /// assert_eq!(&map["v"], &1);
+    /// assert_eq!(&map["w"], &2);
+    /// assert_eq!(&map["x"], &3);
+    /// assert_eq!(&map["y"], &4);
+    /// assert_eq!(&map["z"], &5);
+    /// ```
+    pub fn collapse_into(mut self, src: &mut HashMap<K, V, S>) {
+        loop {
+            if self.inner.is_empty() { break; }
+
+            let mut next = self.inner.pop_back().unwrap();
+
+            if !self.inner.is_empty() {
+                next.discard_private(|_| true);
+            }
+
+            for key in next.buried() {
+                src.remove(key);
+            }
+
+            src.extend(next.map);
+        }
+    }
+
+    /// Merges all contexts in place, without changing any lookups.
+    ///
+    /// Contexts are merged into a single one unless there are barriers or shadowing private
+    /// entries, which are kept apart as in [ContextManager::squash]. Equivalent to
+    /// `manager.squash(..)`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1), ("x", 2)]));
+    /// manager.push(HashMap::from([("w", 3)]));
+    ///
+    /// manager.compact();
+    ///
+    /// assert_eq!(manager.len(), 1);
+    /// assert_eq!(&manager["w"], &3);
+    /// assert_eq!(&manager["x"], &2);
+    ///
+    /// manager.push_empty();
+    /// manager.insert_private("x", 4);
+    /// manager.push_empty();
+    /// manager.compact();
+    ///
+    /// assert_eq!(&manager["x"], &2);
+    ///
+    /// manager.push_empty();
+    /// manager.insert_private("x", 5);
+    /// manager.compact();
+    ///
+    /// assert_eq!(manager.len(), 2);
+    /// assert_eq!(&manager["x"], &5);
+    ///
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(&manager["x"], &2);
+    /// ```
+    pub fn compact(&mut self) { self.squash(..) }
+
+    /// Whether a key is present in the context.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::with_capacity(3);
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("x", 2)]));
+    ///
+    /// assert!(manager.contains_key(&"w"));
+    /// assert!(manager.contains_key(&"x"));
+    /// assert!(!manager.contains_key(&"y"));
+    /// ```
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.access(key).is_some()
+    }
+
+    /// Whether a key is present in the local context
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    ///
+    /// assert!(manager.contains_local_key("w"));
+    ///
+    /// manager.push_empty();
+    ///
+    /// assert!(!manager.contains_local_key("w"));
+    /// ```
+    pub fn contains_local_key<Q>(&self, key: &Q) -> bool
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        !self.inner.is_empty() && self.inner[0].map.contains_key(key)
+    }
+
+    /// Returns a mutable reference to the context at `index`.
+    ///
+    /// Entries inserted through the returned map are inheritable, unless their key was a private
+    /// entry of the context when it was borrowed.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("x", 2)]));
+    ///
+    /// if let Some(context) = manager.context_mut(1) {
+    ///     context.insert("y", 3);
+    /// }
+    ///
+    /// assert_eq!(manager.get_from(1, "y"), Some(&3));
+    /// assert_eq!(manager.context_mut(2), None);
+    ///
+    /// manager.insert_private("z", 4);
+    /// manager.context_mut(0).unwrap().remove("z");
+    /// manager.context_mut(0).unwrap().insert("z", 5);
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(manager.get("z"), Some(&5));
+    /// ```
+    pub fn context_mut(&mut self, index: usize) -> Option<&mut HashMap<K, V, S>> {
+        self.inner.get_mut(index).map(|frame| {
+            frame.drop_stale_private();
+
+            &mut frame.map
+        })
+    }
+
+    /// Returns every binding visible from the context at `index`.
+    fn effective(&self, index: usize) -> HashMap<&K, &V> {
+        let mut bindings = HashMap::new();
+
+        for (_, key, value) in self.bindings(index) {
+            bindings.entry(key).or_insert(value);
+        }
+
+        bindings
+    }
+
+    /// Describes how `key` resolves from the local context, listing every context searched and
+    /// the value each one holds.
+    ///
+    /// Contexts hidden by a barrier are not searched, and private entries of outer contexts are
+    /// not considered held.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use std::hash::RandomState;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::<&str, i32, RandomState, &str>::default();
+    ///
+    /// manager.push_with_metadata(HashMap::from([("port", 80)]), "defaults");
+    /// manager.push_with_metadata(HashMap::from([("port", 8080)]), "system");
+    /// manager.push_with_metadata(HashMap::new(), "user");
+    ///
+    /// let lookup = manager.explain("port");
+    ///
+    /// assert_eq!(lookup.searched.len(), 3);
+    /// assert_eq!(lookup.value(), Some(&8080));
+    /// assert_eq!(lookup.depth(), Some(1));
+    /// assert_eq!(lookup.metadata(), Some(&"system"));
+    /// assert_eq!(lookup.shadowed().collect::<Vec<_>>(), vec![&80]);
+    /// ```
+    pub fn explain<Q>(&self, key: &Q) -> Lookup<'_, K, V, M>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        let mut winner = None;
+
+        let searched = self
+            .search(0, key)
+            .map(|(depth, frame)| {
+                let entry = frame.lookup_key_value(key, depth == 0);
+
+                winner = winner.or(entry.map(|(key, _)| key));
+
+                Layer { depth, metadata: &frame.meta, value: entry.map(|(_, value)| value) }
+            })
+            .collect();
+
+        Lookup { key: winner, searched }
+    }
+
+    /// Returns a reference to the value associated with `key`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::with_capacity(3);
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("x", 2)]));
+    ///
+    /// assert_eq!(manager.get(&"w"), Some(&1));
+    /// assert_eq!(manager.get(&"x"), Some(&2));
+    /// assert_eq!(manager.get(&"y"), None);
+    /// ```
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.access(key).map(|(_, value)| value)
+    }
+
+    /// Returns a vector of references to all values associated with `key`, ordered by
+    /// precedence.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::with_capacity(3);
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    /// manager.push(HashMap::from([("w", 3)]));
+    ///
+    /// assert_eq!(manager.get_all(&"w"), vec![&3, &2, &1]);
+    /// assert_eq!(manager.get_all(&"x"), Vec::<&i32>::new());
+    /// ```
+    pub fn get_all<Q>(&self, key: &Q) -> Vec<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.search(0, key).filter_map(|(index, ctx)| ctx.lookup(key, index == 0)).collect()
+    }
+
+    /// Returns a reference to the value associated with `key` starting with the context at `index`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::with_capacity(3);
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("x", 2)]));
+    /// manager.push(HashMap::from([("w", 3)]));
+    ///
+    /// assert_eq!(manager.get_from(0, &"w"), Some(&3));
+    /// assert_eq!(manager.get_from(1, &"w"), Some(&1));
+    /// assert_eq!(manager.get_from(2, &"w"), Some(&1));
+    /// assert_eq!(manager.get_from(3, &"w"), None);
+    /// ```
+    pub fn get_from<Q>(&self, index: usize, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.search(index, key).find_map(|(index, ctx)| ctx.lookup(key, index == 0))
+    }
+
+    /// Returns a reference to the value associated with `key` in the global context.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// manager.push(HashMap::from([("w", 2)]));
+    ///
+    /// assert_eq!(manager.get_global("w"), Some(&1));
+    /// assert_eq!(manager.get_global("x"), None);
+    /// ```
+    pub fn get_global<Q>(&self, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.inner.back().and_then(|frame| frame.map.get(key))
+    }
+
+    /// Returns a reference to the value associated with `key`, searching past barriers.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("x", 2)]));
+    /// manager.push_barrier(HashMap::new());
+    ///
+    /// assert_eq!(manager.get("x"), None);
+    /// assert_eq!(manager.get_ignoring_barriers("x"), Some(&2));
+    /// assert_eq!(manager.get_ignoring_barriers("w"), Some(&1));
+    /// ```
+    pub fn get_ignoring_barriers<Q>(&self, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        (0..)
+            .zip(&self.inner)
+            .take_while(|(_, ctx)| !ctx.hides(key))
+            .find_map(|(index, ctx)| ctx.lookup(key, index == 0))
+    }
+
+    /// Returns a reference to the value associated with `key` as seen from a context above the
+    /// local one.
+    pub(crate) fn get_inherited<Q>(&self, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.search(0, key).find_map(|(_, ctx)| ctx.lookup(key, false))
+    }
+
+    /// Returns a reference to the value associated with `key` in the local context.
+    ///
+    /// # Example
+    ///```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// assert_eq!(manager.get_local(&"w"), Some(&1));
+    ///
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(manager.get_local(&"w"), None);
+    /// ```
+    pub fn get_local<Q>(&self, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        if self.inner.is_empty() { None } else { self.inner[0].map.get(key) }
+    }
+
+    /// Returns a mutable reference to the value associated with `key` in the local context.
+    ///
+    /// # Example
+    ///```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// if let Some(w) = manager.get_local_mut("w") {
+    ///     *w = 2;
+    /// }
+    ///
+    /// assert_eq!(manager.get(&"w"), Some(&2));
+    ///
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(manager.get_local_mut(&"w"), None);
+    /// ```
+    pub fn get_local_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        if self.inner.is_empty() { None } else { self.inner[0].map.get_mut(key) }
+    }
+
+    /// Returns a mutable reference to the value associated with `key`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// if let Some(v) = manager.get_mut("w") {
+    ///     *v = 2;
+    /// }
+    ///
+    /// assert_eq!(&manager["w"], &2);
+    /// assert_eq!(manager.get_mut(&"x"), None);
+    /// ```
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        if self.access_tracking {
+            self.access(key);
+        }
+
+        self.search_mut(0, key).find_map(|(index, ctx)| ctx.lookup_mut(key, index == 0))
+    }
+
+    /// Returns a reference to the value associated with `key` starting with the context at `index`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    ///
+    /// if let Some(v) = manager.get_mut_from(1, "w") {
+    ///     *v = 3;
+    /// }
+    ///
+    /// assert_eq!(manager.get_from(0, "w"), Some(&2));
+    /// assert_eq!(manager.get_from(1, "w"), Some(&3));
+    /// assert_eq!(manager.get_mut_from(2, "w"), None);
+    /// ```
+    pub fn get_mut_from<Q>(&mut self, index: usize, key: &Q) -> Option<&mut V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.search_mut(index, key).find_map(|(index, ctx)| ctx.lookup_mut(key, index == 0))
+    }
+
+    /// Associates `value` with `key` in the local context if there is one.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// assert_eq!(manager.insert("w", 1), None);
+    /// assert_eq!(manager.get("w"), None);
+    ///
+    /// manager.push_empty();
+    ///
+    /// manager.insert("w", 1);
+    ///
+    /// assert_eq!(manager.insert("w", 2), Some(1));
+    ///
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(manager.insert("w", 3), None);
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if self.inner.is_empty() { None } else { self.inner[0].insert(key, value) }
+    }
+
+    /// Associates `value` with `key` in the context at `index` if there is one.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(manager.insert_at(1, "w", 2), Some(1));
+    /// assert_eq!(manager.insert_at(2, "w", 3), None);
+    /// assert_eq!(manager.get_local("w"), None);
+    /// assert_eq!(&manager["w"], &2);
+    /// ```
+    pub fn insert_at(&mut self, index: usize, key: K, value: V) -> Option<V> {
+        self.inner.get_mut(index).and_then(|frame| frame.insert(key, value))
+    }
+
+    /// Associates `value` with `key` in the local context if there is one, reporting how the
+    /// binding relates to those already visible.
+    ///
+    /// A key bound in the local context is replaced. Otherwise, if the key is visible from an
+    /// outer context, the new binding shadows it, which the [ShadowPolicy] of the manager may
+    /// reject. Returns `None` if there is no local context.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::{ContextManager, InsertOutcome, ShadowPolicy};
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(manager.insert_checked("x", 2), Some(InsertOutcome::New));
+    /// assert_eq!(manager.insert_checked("x", 3), Some(InsertOutcome::Replaced(2)));
+    /// assert_eq!(manager.insert_checked("w", 4), Some(InsertOutcome::Shadowed { depth: 1 }));
+    ///
+    /// manager.push_empty();
+    /// manager.set_shadow_policy(ShadowPolicy::Reject);
+    ///
+    /// assert_eq!(
+    ///     manager.insert_checked("x", 5),
+    ///     Some(InsertOutcome::Rejected { depth: 1, value: 5 })
+    /// );
+    /// assert_eq!(&manager["x"], &3);
+    /// ```
+    pub fn insert_checked(&mut self, key: K, value: V) -> Option<InsertOutcome<V>> {
+        let local = self.inner.front()?;
+
+        if local.map.contains_key(&key) {
+            return self.inner[0].insert(key, value).map(InsertOutcome::Replaced);
+        }
+
+        let shadowed = self
+            .search(0, &key)
+            .find(|(index, ctx)| *index > 0 && ctx.lookup(&key, false).is_some())
+            .map(|(index, _)| index);
+
+        let outcome = match (shadowed, self.shadow_policy) {
+            (None, _) => InsertOutcome::New,
+            (Some(depth), ShadowPolicy::Reject) => {
+                return Some(InsertOutcome::Rejected { depth, value });
+            },
+            (Some(depth), ShadowPolicy::Allow) => InsertOutcome::Shadowed { depth }
+        };
+
+        self.inner[0].insert(key, value);
+
+        Some(outcome)
+    }
+
+    /// Associates `value` with `key` in the global context if there is one.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// assert_eq!(manager.insert_global("w", 1), None);
+    /// assert_eq!(manager.get("w"), None);
+    ///
+    /// manager.push_empty();
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(manager.insert_global("w", 1), None);
+    /// assert_eq!(manager.insert_global("w", 2), Some(1));
+    /// assert_eq!(manager.get_local("w"), None);
+    /// assert_eq!(&manager["w"], &2);
+    /// ```
+    pub fn insert_global(&mut self, key: K, value: V) -> Option<V> {
+        self.inner.back_mut().and_then(|frame| frame.insert(key, value))
+    }
+
+    /// Associates `value` with `key` in the local context as a private entry, if there is a
+    /// local context.
+    ///
+    /// Private entries are only visible while their context is the local one. Lookups skip them
+    /// once another context is pushed, and they become visible again when it is popped.
+    /// Inserting the key again with [ContextManager::insert] makes the entry inheritable.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(manager.insert_private("w", 2), None);
+    /// assert_eq!(&manager["w"], &2);
+    ///
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(&manager["w"], &1);
+    ///
+    /// manager.pop();
+    ///
+    /// assert_eq!(&manager["w"], &2);
+    /// ```
+    pub fn insert_private(&mut self, key: K, value: V) -> Option<V>
+    where K: Clone, S: Clone {
+        self.inner.front_mut().and_then(|frame| frame.insert_private(key, value))
+    }
+
+    /// Whether `key` is a private entry of the local context.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::with_empty();
+    ///
+    /// manager.insert_private("w", 1);
+    /// manager.insert("x", 2);
+    ///
+    /// assert!(manager.is_private("w"));
+    /// assert!(!manager.is_private("x"));
+    ///
+    /// manager.insert("w", 3);
+    ///
+    /// assert!(!manager.is_private("w"));
+    /// ```
+    pub fn is_private<Q>(&self, key: &Q) -> bool
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.inner.front().is_some_and(|frame| frame.is_private(key))
+    }
+
+    /// Returns every key visible from the local context that is bound in more than one of the
+    /// searched contexts, along with its values ordered by precedence.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("host", "localhost"), ("port", "80")]);
+    ///
+    /// manager.push(HashMap::from([("port", "8080"), ("user", "admin")]));
+    /// manager.push(HashMap::from([("port", "8080")]));
+    ///
+    /// let overrides = manager.overrides();
+    ///
+    /// assert_eq!(overrides.len(), 1);
+    /// assert_eq!(overrides[&"port"], vec![&"8080", &"8080", &"80"]);
+    /// ```
+    pub fn overrides(&self) -> HashMap<&K, Vec<&V>> {
+        let mut overrides: HashMap<&K, Vec<&V>> = HashMap::new();
+
+        for (_, key, value) in self.bindings(0) {
+            overrides.entry(key).or_default().push(value);
+        }
+
+        overrides.retain(|_, values| values.len() > 1);
+        overrides
+    }
+
+    /// Removes the local context after moving the entries for `keys`, which must be present in
+    /// it, into the next context.
+    fn pop_exported(&mut self, keys: Vec<K>, policy: ConflictPolicy)
+        -> Result<Option<HashMap<K, V, S>>, ConflictError<K>>
+    where K: Clone {
+        if self.inner.len() < 2 {
+            return Ok(self.pop());
+        }
+
+        if policy == ConflictPolicy::Error {
+            let conflicts: Vec<K> = keys
+                .iter()
+                .filter(|key| self.inner[1].map.contains_key(*key))
+                .cloned()
+                .collect();
+
+            if !conflicts.is_empty() {
+                return Err(ConflictError { keys: conflicts });
+            }
+        }
+
+        let Some(mut frame) = self.inner.pop_front() else { return Ok(None) };
+        let parent = &mut self.inner[0];
+
+        for key in keys {
+            if policy == ConflictPolicy::Keep && parent.map.contains_key(&key) {
+                continue;
+            }
+
+            if let Some((key, value)) = frame.map.remove_entry(&key) {
+                parent.insert(key, value);
+            }
+        }
+
+        Ok(Some(frame.map))
+    }
+
+    /// Removes the local context, moving the entries for `keys` into the context that becomes
+    /// local, and returns the remaining entries.
+    ///
+    /// Keys already present in the new local context are handled according to `policy`. Entries
+    /// kept back by [ConflictPolicy::Keep] stay in the returned context, and a conflict under
+    /// [ConflictPolicy::Error] leaves the manager unchanged. If the local context is the only
+    /// one, it is popped without exporting anything.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::{ConflictError, ConflictPolicy, ContextManager};
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// manager.push(HashMap::from([("w", 2), ("x", 3), ("y", 4)]));
+    ///
+    /// assert_eq!(
+    ///     manager.pop_exporting(["w", "x"], ConflictPolicy::Error),
+    ///     Err(ConflictError { keys: vec!["w"] })
+    /// );
+    /// assert_eq!(manager.len(), 2);
+    ///
+    /// let rest = manager.pop_exporting(["w", "x"], ConflictPolicy::Keep).unwrap().unwrap();
+    ///
+    /// assert_eq!(rest, HashMap::from([("w", 2), ("y", 4)]));
+    /// assert_eq!(&manager["w"], &1);
+    /// assert_eq!(&manager["x"], &3);
+    /// ```
+    pub fn pop_exporting<'a, I, Q>(&mut self, keys: I, policy: ConflictPolicy)
+        -> Result<Option<HashMap<K, V, S>>, ConflictError<K>>
+    where I: IntoIterator<Item = &'a Q>, K: Borrow<Q> + Clone, Q: 'a + ?Sized + Hash + Eq {
+        let keys = match self.inner.front() {
+            Some(frame) => keys
+                .into_iter()
+                .filter_map(|key| frame.map.get_key_value(key))
+                .map(|(key, _)| key.clone())
+                .collect(),
+            None => Vec::new()
+        };
+
+        self.pop_exported(keys, policy)
+    }
+
+    /// Removes the local context, moving the entries that satisfy `predicate` into the context
+    /// that becomes local, and returns the remaining entries.
+    ///
+    /// Conflicts are handled as in [ContextManager::pop_exporting].
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::{ConflictPolicy, ContextManager};
+    /// let mut manager = ContextManager::from([("w", 1)]);
+    ///
+    /// manager.push(HashMap::from([("w", 2), ("_x", 3), ("y", 4)]));
+    ///
+    /// let exported = |key: &&str, _: &i32| !key.starts_with('_');
+    /// let rest = manager.pop_exporting_where(exported, ConflictPolicy::Overwrite);
+    ///
+    /// assert_eq!(rest, Ok(Some(HashMap::from([("_x", 3)]))));
+    /// assert_eq!(&manager["w"], &2);
+    /// assert_eq!(&manager["y"], &4);
+    /// ```
+    pub fn pop_exporting_where<F>(&mut self, mut predicate: F, policy: ConflictPolicy)
+        -> Result<Option<HashMap<K, V, S>>, ConflictError<K>>
+    where F: FnMut(&K, &V) -> bool, K: Clone {
+        let keys = match self.inner.front() {
+            Some(frame) => frame.map
+                .iter()
+                .filter(|(key, value)| predicate(key, value))
+                .map(|(key, _)| key.clone())
+                .collect(),
+            None => Vec::new()
+        };
+
+        self.pop_exported(keys, policy)
+    }
+
+    /// Removes the local context if one is present, along with the keys of its entries that
+    /// were never read.
+    ///
+    /// Only reads counted while access tracking was enabled are considered, so every entry is
+    /// reported if it never was. Contexts cloned from another one, such as by
+    /// [ContextManager::push_local] or forks, start without any counted reads, unlike the
+    /// contexts of a cloned manager.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.set_access_tracking(true);
+    /// manager.push(HashMap::from([("used", 1), ("unused", 2)]));
+    ///
+    /// assert_eq!(&manager["used"], &1);
+    ///
+    /// let (context, unused) = manager.pop_with_report().unwrap();
+    ///
+    /// assert_eq!(context.len(), 2);
+    /// assert_eq!(unused, vec!["unused"]);
+    /// assert_eq!(manager.pop_with_report(), None);
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    ///
+    /// assert_eq!(&manager["w"], &1);
+    ///
+    /// manager.push_local();
+    ///
+    /// assert_eq!(manager.pop_with_report().unwrap().1, vec!["w"]);
+    /// ```
+    pub fn pop_with_report(&mut self) -> Option<(HashMap<K, V, S>, Vec<K>)>
+    where K: Clone {
+        let frame = self.inner.pop_front()?;
+        let reads = self.reads.take(frame.id);
+        let unused = frame.map
+            .keys()
+            .filter(|key| !reads.contains_key(&frame.key_hash(*key)))
+            .cloned()
+            .collect();
+
+        Some((frame.map, unused))
+    }
+
+    /// Removes `key` from the local context if one is present.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::with_capacity(3);
+    ///
+    /// assert_eq!(manager.remove("w"), None);
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    ///
+    /// assert_eq!(manager.remove("w"), Some(2));
+    /// assert_eq!(manager.remove("w"), None);
+    /// assert_eq!(&manager["w"], &1);
+    /// ```
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.remove_entry(0, key)
+    }
+
+    /// Removes all instances of `key` from the context manager, returning a vector of the values,
+    /// ordered by precedence.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::with_capacity(3);
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    /// manager.push(HashMap::from([("w", 3)]));
+    ///
+    /// assert_eq!(manager.remove_all(&"w"), vec![3, 2, 1]);
+    /// assert_eq!(manager.remove_all(&"x"), vec![]);
+    /// ```
+    pub fn remove_all<Q>(&mut self, key: &Q) -> Vec<V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        (0..self.inner.len()).filter_map(|index| self.remove_entry(index, key)).collect()
+    }
+
+    /// Removes `key` from the context at `index` if there is one.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    ///
+    /// assert_eq!(manager.remove_at(1, "w"), Some(1));
+    /// assert_eq!(manager.remove_at(1, "w"), None);
+    /// assert_eq!(manager.remove_at(2, "w"), None);
+    /// assert_eq!(&manager["w"], &2);
+    /// ```
+    pub fn remove_at<Q>(&mut self, index: usize, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.remove_entry(index, key)
+    }
+
+    /// Removes `key` from the global context if one is present.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// assert_eq!(manager.remove_global("w"), None);
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    ///
+    /// assert_eq!(manager.remove_global("w"), Some(1));
+    /// assert_eq!(manager.remove_global("w"), None);
+    /// assert_eq!(&manager["w"], &2);
+    /// ```
+    pub fn remove_global<Q>(&mut self, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.inner.len().checked_sub(1).and_then(|index| self.remove_entry(index, key))
+    }
+
+    /// Removes `key` from the context at `index` along with the counted reads of its entry.
+    fn remove_entry<Q>(&mut self, index: usize, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        let frame = self.inner.get_mut(index)?;
+
+        self.reads.forget(frame.id, frame.key_hash(key));
+        frame.remove(key)
+    }
+
+    /// Returns the contexts searched for `key` by lookups starting at `index` along with their
+    /// indices, ordered by precedence and stopping before a context that removed `key`.
+    fn search<'a, Q>(&'a self, index: usize, key: &Q)
+        -> impl Iterator<Item = (usize, &'a Frame<K, V, S, M>)>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.visible(index).take_while(move |(_, frame)| !frame.hides(key))
+    }
+
+    /// Returns the contexts searched for `key` by lookups starting at `index` along with their
+    /// indices, ordered by precedence and stopping before a context that removed `key`.
+    fn search_mut<'a, Q>(&'a mut self, index: usize, key: &Q)
+        -> impl Iterator<Item = (usize, &'a mut Frame<K, V, S, M>)>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.visible_mut(index).take_while(move |(_, frame)| !frame.hides(key))
+    }
+
+    /// Returns a mutable reference to the local context alongside a read-only view into the
+    /// remaining contexts searched by lookups, if there is a local context.
+    ///
+    /// The view leaves out the contexts hidden by barriers, so it sees the same bindings as
+    /// lookups from the local context. Entries are inserted through the returned map as in
+    /// [ContextManager::context_mut].
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("y", 1)]);
+    ///
+    /// manager.push_empty();
+    /// manager.insert_private("y", 100);
+    /// manager.push_empty();
+    ///
+    /// if let Some((local, parents)) = manager.split_local_mut() {
+    ///     local.insert("x", parents["y"] + 1);
+    /// }
+    ///
+    /// assert_eq!(manager.get_local("x"), Some(&2));
+    ///
+    /// manager.push_barrier(HashMap::new());
+    ///
+    /// if let Some((_, parents)) = manager.split_local_mut() {
+    ///     assert_eq!(parents.get("x"), None);
+    ///     assert_eq!(parents["y"], 1);
+    /// }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn split_local_mut(&mut self)
+        -> Option<(&mut HashMap<K, V, S>, ContextView<'_, K, V, S, M>)> {
+        let (end, resume) = self.search_bounds(0);
+        let (local, rest) = self.inner.make_contiguous().split_first_mut()?;
+        let rest = &*rest;
+
+        local.drop_stale_private();
+
+        Some((&mut local.map, ContextView::new(&rest[..end - 1], &rest[resume - 1..], false)))
+    }
+
+    /// Merges the contexts in `range` into a single context, without changing any lookups.
+    ///
+    /// Keys keep the value from the most local context in `range` that holds them, and the
+    /// merged context takes the metadata of the most local context merged into it. Barriers keep
+    /// hiding the same contexts, as each barrier in `range` ends the context it is merged into
+    /// and the global contexts are kept apart if lookups can pass a barrier. Inherited contexts
+    /// are only merged with each other, so [ContextManager::rebase] still replaces them. Private
+    /// entries are only kept from the most local context in `range`, since the others could only
+    /// be seen while their context was local, and the local context is kept apart if its private
+    /// entries shadow other contexts in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` starts after it ends or ends after the last context.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2), ("x", 3)]));
+    /// manager.push(HashMap::from([("w", 4)]));
+    /// manager.push(HashMap::from([("y", 5)]));
+    ///
+    /// manager.squash(1..3);
+    ///
+    /// assert_eq!(manager.len(), 3);
+    /// assert_eq!(manager.get_all("w"), vec![&4, &1]);
+    /// assert_eq!(manager.get_from(1, "x"), Some(&3));
+    /// assert_eq!(&manager["y"], &5);
+    ///
+    /// let mut manager = ContextManager::from([("global", 0)]);
+    ///
+    /// manager.push(HashMap::from([("hidden", 1)]));
+    /// manager.push_barrier(HashMap::new());
+    /// manager.push_empty();
+    ///
+    /// manager.squash(0..3);
+    ///
+    /// assert_eq!(manager.len(), 3);
+    /// assert_eq!(manager.get("hidden"), None);
+    /// assert_eq!(&manager["global"], &0);
+    /// ```
+    pub fn squash<R>(&mut self, range: R)
+    where R: RangeBounds<usize> {
+        let Range { start, mut end } =
+            to_range(range, self.inner.len()).expect("range out of bounds");
+
+        let globals = self.inner.len().saturating_sub(self.global_depth);
+        let barriers = self.inner.range(..globals).any(|frame| frame.barrier);
+
+        for index in (start..end).rev() {
+            if index + 1 >= end {
+                continue;
+            }
+
+            // Only the local context keeps its private entries, which must not replace the
+            // values they shadow.
+            let split = self.inner[index].barrier
+                || self.inner[index].inherited != self.inner[index + 1].inherited
+                || (barriers && index + 1 >= globals)
+                || (index == 0 && self.shadows_private(1..end));
+
+            if split {
+                self.squash_range(index + 1..end);
+                end = index + 1;
+            }
+        }
+
+        self.squash_range(start..end);
+    }
+
+    /// Whether a private entry of the local context shadows a key bound or removed in the
+    /// contexts in `range`.
+    fn shadows_private(&self, range: Range<usize>) -> bool {
+        self.inner.front().and_then(|frame| frame.private.as_ref()).is_some_and(|private| {
+            private.iter().any(|key| {
+                self.inner.range(range.clone()).any(|frame| {
+                    frame.map.contains_key(key) || frame.hides(key)
+                })
+            })
+        })
+    }
+
+    /// Merges the contexts in `range`, none of which is a barrier except the outermost one, into
+    /// a single context.
+    fn squash_range(&mut self, range: Range<usize>) {
+        let (start, len) = (range.start, range.len());
+        let reads = &mut self.reads;
+
+        let squashed = {
+            let mut frames = self.inner
+                .drain(range)
+                .rev()
+                .enumerate()
+                .map(|(position, mut frame)| {
+                    if position + 1 < len {
+                        frame.discard_private(|_| true);
+                    }
+
+                    let counts = reads.take(frame.id);
+
+                    (frame, counts)
+                });
+
+            frames.next().map(|(mut squashed, counts)| {
+                let mut merged: HashMap<u64, usize> = squashed.map
+                    .keys()
+                    .map(|key| squashed.key_hash(key))
+                    .filter_map(|key| counts.get(&key).map(|count| (key, *count)))
+                    .collect();
+
+                for (mut frame, counts) in frames {
+                    // Private entries are hidden while the merged context is not local, so the
+                    // entries they would shadow have to stay visible.
+                    if start > 0 {
+                        frame.discard_private(|key| {
+                            squashed.map.contains_key(key) || squashed.hides(key)
+                        });
+                    }
+
+                    for key in frame.buried() {
+                        merged.remove(&squashed.key_hash(key));
+                        squashed.remove(key);
+                    }
+
+                    // Each entry keeps the counts of the context its value is taken from.
+                    for key in frame.map.keys() {
+                        match counts.get(&frame.key_hash(key)) {
+                            Some(count) => merged.insert(squashed.key_hash(key), *count),
+                            None => merged.remove(&squashed.key_hash(key))
+                        };
+                    }
+
+                    if let Some(tombstones) = &mut squashed.tombstones {
+                        tombstones.retain(|key| !frame.map.contains_key(key));
+                    }
+
+                    if let Some(other) = frame.tombstones {
+                        if let Some(tombstones) = &mut squashed.tombstones {
+                            tombstones.extend(other);
+                        } else {
+                            squashed.tombstones = Some(other);
+                        }
+                    }
+
+                    squashed.map.extend(frame.map);
+                    squashed.meta = frame.meta;
+                    squashed.private = frame.private;
+                }
+
+                (squashed, merged)
+            })
+        };
+
+        if let Some((frame, counts)) = squashed {
+            self.reads.insert(frame.id, counts);
+            self.inner.insert(start, frame);
+        }
+    }
+
+    /// Returns up to `max` visible keys that are close to `key`, best match first.
+    ///
+    /// Keys are ranked by their edit distance to `key`, with ties going to the key bound in the
+    /// more local context and then to the lexicographically smaller key. Keys that differ from
+    /// `key` in more than a third of their characters are not suggested.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("colour", 1), ("size", 2)]);
+    ///
+    /// manager.push(HashMap::from([("color", 3), ("cooler", 4)]));
+    ///
+    /// assert_eq!(manager.suggest("colr", 3), vec![&"color", &"cooler", &"colour"]);
+    /// assert_eq!(manager.suggest("weight", 2), Vec::<&&str>::new());
+    /// ```
+    pub fn suggest(&self, key: &str, max: usize) -> Vec<&K>
+    where K: AsRef<str> {
+        let mut seen = HashSet::new();
+        let mut candidates = Vec::new();
+
+        for (index, candidate, _) in self.bindings(0) {
+            if seen.insert(candidate) {
+                let distance = suggest::edit_distance(key, candidate.as_ref());
+                let longest = key.chars().count().max(candidate.as_ref().chars().count());
+
+                if distance * 3 <= longest {
+                    candidates.push((distance, index, candidate));
+                }
+            }
+        }
+
+        candidates.sort_by(|(a, i, x), (b, j, y)| {
+            (a, i, x.as_ref()).cmp(&(b, j, y.as_ref()))
+        });
+        candidates.into_iter().take(max).map(|(_, _, candidate)| candidate).collect()
+    }
+
+    /// Returns a reference to the value associated with `key`, or an error suggesting up to
+    /// three close keys as found by [ContextManager::suggest].
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::{ContextManager, LookupError};
+    /// let manager = ContextManager::from([("color", 1)]);
+    ///
+    /// assert_eq!(manager.try_get("color"), Ok(&1));
+    ///
+    /// let error = manager.try_get("colr").unwrap_err();
+    ///
+    /// assert_eq!(error, LookupError::NotFound { suggestions: vec![&"color"] });
+    /// assert_eq!(error.to_string(), "key not found, did you mean `color`?");
+    /// ```
+    pub fn try_get<Q>(&self, key: &Q) -> Result<&V, LookupError<'_, K>>
+    where K: Borrow<Q> + AsRef<str>, Q: ?Sized + Hash + Eq + AsRef<str> {
+        self.get(key).ok_or_else(|| {
+            LookupError::NotFound { suggestions: self.suggest(key.as_ref(), 3) }
+        })
+    }
+}
+
+
+impl<K, V, S, M> ContextManager<K, V, S, M>
+where K: Hash + Eq + Clone, V: Clone, S: BuildHasher + Clone, M: Clone {
+    /// Applies `patch` to the local context, returning the patch that reverts it.
+    ///
+    /// Removing a key deletes its local binding, and leaves a tombstone in the local context if
+    /// the key would otherwise still be visible. Applying the returned patch restores the
+    /// bindings visible from the local context, along with which of them are private. If there
+    /// is no local context, nothing is applied and the returned patch is empty.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::{ContextManager, ContextPatch};
+    /// let mut manager = ContextManager::from([("host", "localhost"), ("port", "80")]);
+    ///
+    /// manager.push_empty();
+    ///
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// patch.set("port", "8080");
+    /// patch.update("user", "admin");
+    /// patch.remove("host");
+    ///
+    /// let inverse = manager.apply_patch(patch);
+    ///
+    /// assert_eq!(manager.get("host"), None);
+    /// assert_eq!(&manager["port"], &"8080");
+    /// assert_eq!(manager.get("user"), None);
+    /// assert_eq!(inverse.len(), 2);
+    ///
+    /// manager.insert_private("host", "example.com");
+    ///
+    /// assert_eq!(&manager["host"], &"example.com");
+    ///
+    /// manager.apply_patch(inverse);
+    ///
+    /// assert_eq!(&manager["host"], &"localhost");
+    /// assert_eq!(&manager["port"], &"80");
+    ///
+    /// manager.insert_private("port", "8443");
+    ///
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// patch.set("port", "443");
+    ///
+    /// let inverse = manager.apply_patch(patch);
+    ///
+    /// manager.apply_patch(inverse);
+    ///
+    /// assert_eq!(&manager["port"], &"8443");
+    ///
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(&manager["port"], &"80");
+    /// ```
+    pub fn apply_patch(&mut self, patch: ContextPatch<K, V, S>) -> ContextPatch<K, V, S> {
+        let mut inverse = ContextPatch::with_hasher(patch.sets.hasher().clone());
+
+        if self.inner.is_empty() {
+            return inverse;
+        }
+
+        for (key, value) in patch.sets {
+            self.patch_entry(key, Some(value), false, &mut inverse);
+        }
+
+        for (key, value) in patch.private {
+            self.patch_entry(key, Some(value), true, &mut inverse);
+        }
+
+        for (key, value) in patch.updates {
+            if self.get_from(0, &key).is_some() {
+                self.patch_entry(key, Some(value), false, &mut inverse);
+            }
+        }
+
+        for key in patch.removals {
+            self.patch_entry(key, None, false, &mut inverse);
+        }
+
+        inverse
     }
 
-    /// Returns a reference to the value associated with `key` starting with the context at `index`.
+    /// Creates a map holding a clone of the visible binding of each key in `keys`, along with
+    /// the keys that are not visible from the local context.
     ///
     /// # Example
     /// ```
     /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::new();
+    /// let mut manager = ContextManager::from([("w", 1), ("x", 2)]);
     ///
-    /// manager.push(HashMap::from([("w", 1)]));
-    /// manager.push(HashMap::from([("w", 2)]));
+    /// manager.push(HashMap::from([("x", 3), ("y", 4)]));
     ///
-    /// if let Some(v) = manager.get_mut_from(1, "w") {
-    ///     *v = 3;
-    /// }
+    /// let (captured, missing) = manager.capture(["x", "y", "z"]);
     ///
-    /// assert_eq!(manager.get_from(0, "w"), Some(&2));
-    /// assert_eq!(manager.get_from(1, "w"), Some(&3));
-    /// assert_eq!(manager.get_mut_from(2, "w"), None);
+    /// assert_eq!(captured, HashMap::from([("x", 3), ("y", 4)]));
+    /// assert_eq!(missing, vec!["z"]);
     /// ```
-    pub fn get_mut_from<Q>(&mut self, index: usize, key: &Q) -> Option<&mut V>
-    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
-        self.inner.range_mut(index..).find_map(|ctx| ctx.get_mut(key))
+    pub fn capture<'a, I, Q>(&self, keys: I) -> (HashMap<K, V, S>, Vec<&'a Q>)
+    where I: IntoIterator<Item = &'a Q>, K: Borrow<Q>, Q: 'a + ?Sized + Hash + Eq, S: Default {
+        let mut captured = HashMap::with_hasher(self.hasher());
+        let mut missing = Vec::new();
+
+        for key in keys {
+            let binding = self
+                .search(0, key)
+                .find_map(|(index, ctx)| ctx.lookup_key_value(key, index == 0));
+
+            match binding {
+                Some((key, value)) => { captured.insert(key.clone(), value.clone()); },
+                None => missing.push(key)
+            }
+        }
+
+        (captured, missing)
     }
 
-    /// Associates `value` with `key` in the local context if there is one.
+    /// Creates a map holding a clone of every visible binding that satisfies `predicate`.
+    ///
+    /// Only the binding visible from the local context is tested for each key, so shadowed
+    /// bindings are never captured.
     ///
     /// # Example
     /// ```
+    /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::new();
-    ///
-    /// assert_eq!(manager.insert("w", 1), None);
-    /// assert_eq!(manager.get("w"), None);
-    ///
-    /// manager.push_empty();
-    ///
-    /// manager.insert("w", 1);
+    /// let mut manager = ContextManager::from([("w", 1), ("x", 2)]);
     ///
-    /// assert_eq!(manager.insert("w", 2), Some(1));
+    /// manager.push(HashMap::from([("x", 3), ("y", 4)]));
     ///
-    /// manager.push_empty();
+    /// let captured = manager.capture_where(|_, value| value % 2 == 1);
     ///
-    /// assert_eq!(manager.insert("w", 3), None);
+    /// assert_eq!(captured, HashMap::from([("w", 1), ("x", 3)]));
     /// ```
-    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        if self.inner.is_empty() { None } else { self.inner[0].insert(key, value) }
+    pub fn capture_where<F>(&self, mut predicate: F) -> HashMap<K, V, S>
+    where F: FnMut(&K, &V) -> bool, S: Default {
+        let mut captured = HashMap::with_hasher(self.hasher());
+        let mut seen = HashSet::new();
+
+        for (_, key, value) in self.bindings(0) {
+            if seen.insert(key) && predicate(key, value) {
+                captured.insert(key.clone(), value.clone());
+            }
+        }
+
+        captured
     }
 
-    /// Removes `key` from the local context if one is present.
+    /// Creates a new context manager initialized with a clone of the current local context.
+    ///
+    /// Equivalent to `manager.fork_from(0)`
     ///
     /// # Example
     /// ```
@@ -572,69 +2631,119 @@ where K: Hash + Eq, S: BuildHasher {
     /// # use contexts::ContextManager;
     /// let mut manager = ContextManager::with_capacity(3);
     ///
-    /// assert_eq!(manager.remove("w"), None);
+    /// assert_eq!(manager.fork(), None);
     ///
     /// manager.push(HashMap::from([("w", 1)]));
-    /// manager.push(HashMap::from([("w", 2)]));
+    /// manager.push(HashMap::from([("x", 2)]));
+    /// manager.push(HashMap::from([("y", 3)]));
     ///
-    /// assert_eq!(manager.remove("w"), Some(2));
-    /// assert_eq!(manager.remove("w"), None);
-    /// assert_eq!(&manager["w"], &1);
+    /// let forked = manager.fork().unwrap();
+    ///
+    /// assert_eq!(forked.get("w"), None);
+    /// assert_eq!(forked.get("x"), None);
+    /// assert_eq!(&forked["y"], &3);
     /// ```
-    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
-    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
-        if self.inner.is_empty() { None } else { self.inner[0].remove(key) }
+    pub fn fork(&self) -> Option<ContextManager<K, V, S, M>> {
+        if self.inner.is_empty() {
+            None
+        } else {
+            Some(self.forked(VecDeque::from([self.inner[0].clone()])))
+        }
     }
 
-    /// Removes all instances of `key` from the context manager, returning a vector of the values,
-    /// ordered by precedence.
+    /// Creates a new context manager with clones of all contexts, keeping only the keys that
+    /// satisfy `predicate`.
     ///
-    /// # Examples
+    /// # Example
     /// ```
     /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::with_capacity(3);
+    /// let mut manager = ContextManager::new();
     ///
-    /// manager.push(HashMap::from([("w", 1)]));
-    /// manager.push(HashMap::from([("w", 2)]));
-    /// manager.push(HashMap::from([("w", 3)]));
+    /// manager.push(HashMap::from([("home", 1), ("secret", 2)]));
+    /// manager.push(HashMap::from([("path", 3)]));
     ///
-    /// assert_eq!(manager.remove_all(&"w"), vec![3, 2, 1]);
-    /// assert_eq!(manager.remove_all(&"x"), vec![]);
+    /// let forked = manager.fork_filtered(|key| *key != "secret");
+    ///
+    /// assert_eq!(forked.len(), 2);
+    /// assert_eq!(&forked["home"], &1);
+    /// assert_eq!(&forked["path"], &3);
+    /// assert_eq!(forked.get("secret"), None);
     /// ```
-    pub fn remove_all<Q>(&mut self, key: &Q) -> Vec<V>
-    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
-        self.inner.iter_mut().filter_map(|ctx| ctx.remove(key)).collect()
+    pub fn fork_filtered<F>(&self, mut predicate: F) -> ContextManager<K, V, S, M>
+    where F: FnMut(&K) -> bool {
+        self.forked(self.inner
+            .iter()
+            .map(|frame| {
+                let mut map = HashMap::with_hasher(frame.map.hasher().clone());
+
+                map.extend(frame.map
+                    .iter()
+                    .filter(|(key, _)| predicate(key))
+                    .map(|(key, value)| (key.clone(), value.clone())));
+
+                let private = frame.private.clone().map(|mut private| {
+                    private.retain(|key| map.contains_key(key));
+
+                    private
+                });
+
+                Frame {
+                    barrier: frame.barrier,
+                    private,
+                    tombstones: frame.tombstones.clone(),
+                    ..Frame::new(map, frame.meta.clone())
+                }
+            })
+            .collect())
     }
-}
 
-
-impl<K, V, S> ContextManager<K, V, S>
-where K: Hash + Eq + Clone, V: Clone, S: BuildHasher + Clone {
-    /// Creates a new context manager initialized with a clone of the current local context.
+    /// Creates a new context manager with a single context holding every key visible from the
+    /// local context and its value.
     ///
-    /// Equivalent to `manager.fork_from(0)`
+    /// The new context has the metadata and private entries of the local context. Forking an
+    /// empty manager creates an empty manager.
     ///
     /// # Example
     /// ```
     /// # use std::collections::HashMap;
     /// # use contexts::ContextManager;
-    /// let mut manager = ContextManager::with_capacity(3);
-    ///
-    /// assert_eq!(manager.fork(), None);
+    /// let mut manager = ContextManager::new();
     ///
-    /// manager.push(HashMap::from([("w", 1)]));
-    /// manager.push(HashMap::from([("x", 2)]));
-    /// manager.push(HashMap::from([("y", 3)]));
+    /// manager.push(HashMap::from([("w", 1), ("x", 2)]));
+    /// manager.push(HashMap::from([("w", 3)]));
     ///
-    /// let forked = manager.fork().unwrap();
+    /// let forked = manager.fork_flat();
     ///
-    /// assert_eq!(forked.get("w"), None);
-    /// assert_eq!(forked.get("x"), None);
-    /// assert_eq!(&forked["y"], &3);
+    /// assert_eq!(forked.len(), 1);
+    /// assert_eq!(&forked["w"], &3);
+    /// assert_eq!(&forked["x"], &2);
     /// ```
-    pub fn fork(&self) -> Option<ContextManager<K, V, S>> {
-        if self.inner.is_empty() { None } else { Some(ContextManager::from(self.inner[0].clone())) }
+    pub fn fork_flat(&self) -> ContextManager<K, V, S, M> {
+        match self.inner.front() {
+            None => self.forked(VecDeque::new()),
+            Some(local) => {
+                let mut map = HashMap::with_hasher(local.map.hasher().clone());
+                let visible: Vec<_> = self.visible(0).collect();
+
+                for (index, frame) in visible.into_iter().rev() {
+                    for key in frame.buried() {
+                        map.remove(key);
+                    }
+
+                    map.extend(frame.map
+                        .iter()
+                        .filter(|(key, _)| index == 0 || !frame.is_private(*key))
+                        .map(|(key, value)| (key.clone(), value.clone())));
+                }
+
+                let mut flat = Frame::new(map, local.meta.clone());
+
+                flat.private = local.private.clone();
+
+                self.forked(VecDeque::from([flat]))
+            }
+        }
     }
 
     /// Creates a new context manager initialized with clones of all contexts from the local one up
@@ -662,20 +2771,195 @@ where K: Hash + Eq + Clone, V: Clone, S: BuildHasher + Clone {
     ///
     /// assert_eq!(invalid_fork, None);
     /// ```
-    pub fn fork_from(&self, index: usize) -> Option<ContextManager<K, V, S>> {
+    pub fn fork_from(&self, index: usize) -> Option<ContextManager<K, V, S, M>> {
         if index >= self.inner.len() {
             None
         } else {
-            Some(ContextManager {
-                inner: self.inner
-                    .range(0..(index + 1)).map(|ctx| ctx.clone())
-                    .collect()
-            })
+            Some(self.forked(self.inner.range(0..(index + 1)).cloned().collect()))
+        }
+    }
+
+    /// Creates a new context manager initialized with clones of the contexts in `range`.
+    ///
+    /// Returns `None` if `range` starts after it ends or ends after the last context.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("x", 2)]));
+    /// manager.push(HashMap::from([("y", 3)]));
+    ///
+    /// let forked = manager.fork_range(1..3).unwrap();
+    ///
+    /// assert_eq!(&forked["w"], &1);
+    /// assert_eq!(&forked["x"], &2);
+    /// assert_eq!(forked.get("y"), None);
+    /// assert_eq!(manager.fork_range(2..4), None);
+    /// assert_eq!(manager.fork_range(0..=usize::MAX), None);
+    /// ```
+    pub fn fork_range<R>(&self, range: R) -> Option<ContextManager<K, V, S, M>>
+    where R: RangeBounds<usize> {
+        let range = to_range(range, self.inner.len())?;
+
+        Some(self.forked(self.inner.range(range).cloned().collect()))
+    }
+
+    /// Returns a clone of the hasher of the local context, or a default hasher if there is none.
+    fn hasher(&self) -> S
+    where S: Default {
+        self.inner.front().map_or_else(S::default, |frame| frame.map.hasher().clone())
+    }
+
+    /// Brings the changes made in `fork` since `base` into the local context.
+    ///
+    /// The bindings visible from `base`, from this manager and from `fork` are compared. Keys
+    /// only changed by `fork` are set or removed in the local context as in
+    /// [ContextManager::apply_patch]. Keys changed differently by both sides are conflicts:
+    /// [ConflictPolicy::Overwrite] takes the change from `fork`, [ConflictPolicy::Keep] keeps the
+    /// local binding, and [ConflictPolicy::Error] leaves the manager unchanged. Returns the
+    /// conflicts that were resolved, in no particular order.
+    ///
+    /// If this manager has no local context, the changes are brought into a new context with
+    /// default metadata, which is only added if there is a change to bring.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::{ConflictError, ConflictPolicy, ContextManager, MergeConflict};
+    /// let mut manager = ContextManager::from([("w", 1), ("x", 2)]);
+    /// let base = manager.clone();
+    /// let mut fork = manager.fork().unwrap();
+    ///
+    /// fork.insert("w", 3);
+    /// fork.insert("y", 4);
+    /// fork.remove("x");
+    /// manager.insert("w", 5);
+    ///
+    /// assert_eq!(
+    ///     manager.merge_fork(&base, &fork, ConflictPolicy::Error),
+    ///     Err(ConflictError { keys: vec!["w"] })
+    /// );
+    ///
+    /// let conflicts = manager.merge_fork(&base, &fork, ConflictPolicy::Keep).unwrap();
+    ///
+    /// assert_eq!(
+    ///     conflicts,
+    ///     vec![MergeConflict { key: "w", base: Some(1), ours: Some(5), theirs: Some(3) }]
+    /// );
+    /// assert_eq!(&manager["w"], &5);
+    /// assert_eq!(manager.get("x"), None);
+    /// assert_eq!(&manager["y"], &4);
+    ///
+    /// let (mut empty, base) = (ContextManager::default(), ContextManager::new());
+    ///
+    /// assert_eq!(empty.merge_fork(&base, &fork, ConflictPolicy::Error), Ok(vec![]));
+    /// assert_eq!(empty.len(), 1);
+    /// assert_eq!(&empty["y"], &4);
+    /// ```
+    pub fn merge_fork(&mut self, base: &Self, fork: &Self, policy: ConflictPolicy)
+        -> Result<Vec<MergeConflict<K, V>>, ConflictError<K>>
+    where V: PartialEq, M: Default {
+        let frame = self.inner.front().or(fork.inner.front()).or(base.inner.front());
+        let Some(hasher) = frame.map(|frame| frame.map.hasher().clone()) else {
+            return Ok(Vec::new())
+        };
+
+        let mut patch = ContextPatch::with_hasher(hasher);
+        let mut conflicts = Vec::new();
+
+        {
+            let base = base.effective(0);
+            let ours = self.effective(0);
+            let theirs = fork.effective(0);
+
+            let keys: HashSet<&K> = base
+                .keys()
+                .chain(ours.keys())
+                .chain(theirs.keys())
+                .copied()
+                .collect();
+
+            for key in keys {
+                let (b, o, t) = (base.get(key), ours.get(key), theirs.get(key));
+
+                if t == b || o == t {
+                    continue;
+                }
+
+                if o != b {
+                    conflicts.push(MergeConflict {
+                        key: key.clone(),
+                        base: b.map(|value| (*value).clone()),
+                        ours: o.map(|value| (*value).clone()),
+                        theirs: t.map(|value| (*value).clone())
+                    });
+
+                    if policy != ConflictPolicy::Overwrite {
+                        continue;
+                    }
+                }
+
+                match t {
+                    Some(value) => patch.set(key.clone(), (*value).clone()),
+                    None => patch.remove(key.clone())
+                }
+            }
+        }
+
+        if policy == ConflictPolicy::Error && !conflicts.is_empty() {
+            let keys = conflicts.into_iter().map(|conflict| conflict.key).collect();
+
+            return Err(ConflictError { keys });
+        }
+
+        if !self.inner.is_empty() {
+            self.apply_patch(patch);
+        } else if !patch.is_empty() {
+            self.push_patch(patch);
+        }
+
+        Ok(conflicts)
+    }
+
+    /// Binds `key` to `value` in the local context, privately if `private`, or removes it if
+    /// `value` is `None`, and records the change that reverts it in `inverse`.
+    fn patch_entry(
+        &mut self,
+        key: K,
+        value: Option<V>,
+        private: bool,
+        inverse: &mut ContextPatch<K, V, S>
+    ) {
+        let previous = self.get_from(0, &key).cloned();
+        let was_private = self.inner[0].is_private(&key);
+
+        match value {
+            Some(value) if private => { self.inner[0].insert_private(key.clone(), value); },
+            Some(value) => { self.inner[0].insert(key.clone(), value); },
+            None if previous.is_none() => return,
+            None => {
+                self.remove_entry(0, &key);
+
+                if self.get_from(0, &key).is_some() {
+                    self.inner[0].bury(key.clone());
+                }
+            }
+        }
+
+        match previous {
+            Some(previous) if was_private => inverse.set_private(key, previous),
+            Some(previous) => inverse.set(key, previous),
+            None => inverse.remove(key)
         }
     }
 
     /// Adds a new context that is a clone of the local context, if one is present.
     ///
+    /// The new context also receives a clone of the local context's metadata.
+    ///
     /// # Example
     /// ```
     /// # use contexts::ContextManager;
@@ -695,16 +2979,50 @@ where K: Hash + Eq + Clone, V: Clone, S: BuildHasher + Clone {
     /// assert_eq!(&manager["w"], &1);
     /// ```
     pub fn push_local(&mut self) {
-        if self.inner.len() > 0 {
-            let context = self.inner[0].clone();
+        if !self.inner.is_empty() {
+            let context = Frame { barrier: false, inherited: false, ..self.inner[0].clone() };
 
             self.inner.push_front(context);
         }
     }
 
+    /// Adds a new local context holding the changes of `patch`, returning the patch that
+    /// reverts it.
+    ///
+    /// Removals are kept as tombstones in the new context, hiding the keys from the contexts
+    /// beneath it until it is popped.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::{ContextManager, ContextPatch};
+    /// let mut manager = ContextManager::from([("host", "localhost"), ("port", "80")]);
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// patch.update("port", "8080");
+    /// patch.remove("host");
+    ///
+    /// manager.push_patch(patch);
+    ///
+    /// assert_eq!(manager.get("host"), None);
+    /// assert_eq!(&manager["port"], &"8080");
+    ///
+    /// manager.pop();
+    ///
+    /// assert_eq!(&manager["host"], &"localhost");
+    /// assert_eq!(&manager["port"], &"80");
+    /// ```
+    pub fn push_patch(&mut self, patch: ContextPatch<K, V, S>) -> ContextPatch<K, V, S>
+    where M: Default {
+        let context = HashMap::with_hasher(patch.sets.hasher().clone());
+
+        self.inner.push_front(Frame::from(context));
+        self.apply_patch(patch)
+    }
+
     /// Adds a new local context merged with the previous local context.
     ///
-    /// The new context has higher precedence.
+    /// The new context has higher precedence and default metadata. Private entries of the
+    /// previous local context stay private unless `context` binds their key.
     ///
     /// # Example
     /// ```
@@ -722,24 +3040,263 @@ where K: Hash + Eq + Clone, V: Clone, S: BuildHasher + Clone {
     ///
     /// assert_eq!(&manager["w"], &1);
     /// assert_eq!(manager.get("x"), None);
+    ///
+    /// manager.insert_private("p", 5);
+    /// manager.push_with_local(HashMap::new());
+    ///
+    /// assert_eq!(&manager["p"], &5);
+    ///
+    /// manager.push_empty();
+    ///
+    /// assert_eq!(manager.get("p"), None);
     /// ```
-    pub fn push_with_local(&mut self, context: HashMap<K, V, S>) {
+    pub fn push_with_local(&mut self, context: HashMap<K, V, S>)
+    where M: Default {
         if self.inner.is_empty() {
-            self.inner.push_back(context)
+            self.inner.push_back(Frame::from(context))
         } else {
-            let mut plocal = self.inner[0].clone();
+            let mut plocal = self.inner[0].map.clone();
+            let private = self.inner[0].private.clone().map(|mut private| {
+                private.retain(|key| !context.contains_key(key));
+
+                private
+            });
 
             plocal.extend(context);
 
-            self.inner.push_front(plocal);
+            self.inner.push_front(Frame { private, ..Frame::from(plocal) });
+        }
+    }
+
+    /// Replaces the inherited contexts of this manager with clones of the contexts of
+    /// `new_base`, keeping the contexts this manager added since it was forked.
+    ///
+    /// The new contexts take the place of the most local inherited context still present, or go
+    /// beneath every context if none is left. Contexts added beneath them, such as global
+    /// contexts, stay beneath them.
+    ///
+    /// Returns the keys bound in the kept contexts that now shadow a value that differs between
+    /// the old and the new base, in no particular order.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut origin = ContextManager::from([("host", "localhost"), ("port", "80")]);
+    /// let mut session = origin.fork().unwrap();
+    ///
+    /// session.push_empty();
+    /// session.insert("port", "8080");
+    ///
+    /// origin.insert("host", "example.com");
+    /// origin.insert("port", "443");
+    ///
+    /// assert_eq!(session.rebase(&origin), vec!["port"]);
+    /// assert_eq!(session.len(), 2);
+    /// assert_eq!(&session["host"], &"example.com");
+    /// assert_eq!(&session["port"], &"8080");
+    ///
+    /// let mut session = origin.fork().unwrap();
+    ///
+    /// session.pop();
+    /// session.push(HashMap::from([("mine", "yes")]));
+    /// session.rebase(&origin);
+    ///
+    /// assert_eq!(session.len(), 2);
+    /// assert_eq!(&session["mine"], &"yes");
+    /// assert_eq!(&session["host"], &"example.com");
+    ///
+    /// let mut session = origin.fork().unwrap();
+    ///
+    /// session.push_global(HashMap::from([("scheme", "https")]));
+    /// session.rebase(&origin);
+    ///
+    /// assert_eq!(session.len(), 2);
+    /// assert_eq!(session.base_len(), 1);
+    /// assert_eq!(&session["scheme"], &"https");
+    /// assert_eq!(&session["port"], &"443");
+    /// ```
+    pub fn rebase(&mut self, new_base: &Self) -> Vec<K>
+    where V: PartialEq {
+        let first = self.inner.iter().position(|frame| frame.inherited).unwrap_or(self.inner.len());
+        let old: HashMap<K, V> = self
+            .effective(first)
+            .into_iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        let beneath = self.inner.split_off(first);
+
+        self.inner.extend(new_base.inner.iter().map(|frame| {
+            Frame { inherited: true, ..frame.clone() }
+        }));
+        self.inner.extend(beneath.into_iter().filter(|frame| !frame.inherited));
+
+        let changes = ContextDiff::between(old.iter().collect(), self.effective(first));
+        let mut seen = HashSet::new();
+
+        self.bindings(0)
+            .into_iter()
+            .filter(|(_, key, _)| seen.insert(*key))
+            .filter(|(depth, key, _)| {
+                *depth < first
+                    && (changes.added.contains_key(key)
+                        || changes.removed.contains_key(key)
+                        || changes.changed.contains_key(key))
+            })
+            .map(|(_, key, _)| key.clone())
+            .collect()
+    }
+}
+
+impl<K, V, S, M> ContextManager<K, V, S, M>
+where K: Hash + Eq, V: PartialEq, S: BuildHasher {
+    /// Returns the [overrides](ContextManager::overrides) whose values are not all equal.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("host", "localhost"), ("port", "80")]);
+    ///
+    /// manager.push(HashMap::from([("host", "localhost"), ("port", "8080")]));
+    ///
+    /// let conflicts = manager.conflicts();
+    ///
+    /// assert_eq!(conflicts.len(), 1);
+    /// assert_eq!(conflicts[&"port"], vec![&"8080", &"80"]);
+    /// ```
+    pub fn conflicts(&self) -> HashMap<&K, Vec<&V>> {
+        let mut conflicts = self.overrides();
+
+        conflicts.retain(|_, values| values.iter().any(|value| *value != values[0]));
+        conflicts
+    }
+
+    /// Compares the bindings visible from the local contexts of `old` and `new`.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let before = ContextManager::from([("host", "localhost"), ("port", "80")]);
+    /// let mut after = before.clone();
+    ///
+    /// after.push_empty();
+    /// after.insert("port", "8080");
+    /// after.insert("user", "admin");
+    ///
+    /// let diff = ContextManager::diff(&before, &after);
+    ///
+    /// assert_eq!(diff.added.get(&"user"), Some(&&"admin"));
+    /// assert_eq!(diff.changed.get(&"port"), Some(&(&"80", &"8080")));
+    /// assert!(diff.removed.is_empty());
+    /// ```
+    pub fn diff<'a>(old: &'a Self, new: &'a Self) -> ContextDiff<'a, K, V> {
+        ContextDiff::between(old.effective(0), new.effective(0))
+    }
+
+    /// Compares the bindings visible from the context at `old` with those visible from the
+    /// context at `new`.
+    ///
+    /// Lookups from each index behave as in [ContextManager::get_from], and an index past the
+    /// outermost context sees no bindings.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1), ("x", 2)]);
+    ///
+    /// manager.push(HashMap::from([("x", 3), ("y", 4)]));
+    ///
+    /// let diff = manager.diff_frames(1, 0);
+    ///
+    /// assert_eq!(diff.added.get(&"y"), Some(&&4));
+    /// assert_eq!(diff.changed.get(&"x"), Some(&(&2, &3)));
+    /// assert_eq!(diff.removed.len(), 0);
+    /// assert_eq!(manager.diff_frames(2, 1).added.len(), 2);
+    /// assert_eq!(manager.diff_frames(5, 0).added.len(), 3);
+    /// ```
+    pub fn diff_frames(&self, old: usize, new: usize) -> ContextDiff<'_, K, V> {
+        ContextDiff::between(self.effective(old), self.effective(new))
+    }
+
+    /// Removes entries whose value is equal to the value they shadow in an outer context,
+    /// returning the number of entries removed.
+    ///
+    /// Lookups return the same values before and after pruning. Private entries are never removed.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::from([("w", 1), ("x", 2)]);
+    ///
+    /// manager.push_local();
+    /// manager.insert("x", 3);
+    ///
+    /// assert_eq!(manager.prune_redundant(), 1);
+    /// assert_eq!(manager.get_local("w"), None);
+    /// assert_eq!(&manager["w"], &1);
+    /// assert_eq!(&manager["x"], &3);
+    /// ```
+    pub fn prune_redundant(&mut self) -> usize {
+        let globals = self.inner.len().saturating_sub(self.global_depth);
+        let frames = self.inner.make_contiguous();
+        let mut pruned = 0;
+
+        for index in 0..frames.len() {
+            let (inner, outer) = frames.split_at_mut(index + 1);
+            let frame = &mut inner[index];
+            let before = frame.map.len();
+
+            // The contexts searched after this one, with barriers handled as in `search_bounds`.
+            let resume = globals.saturating_sub(index + 1);
+            let end = if frame.barrier && index < globals {
+                0
+            } else {
+                match outer.iter().position(|ctx| ctx.barrier) {
+                    Some(offset) if offset < resume => offset + 1,
+                    _ => outer.len()
+                }
+            };
+            let (searched, skipped) = outer.split_at(end);
+            let resumed = &skipped[resume.saturating_sub(end).min(skipped.len())..];
+
+            let (private, tombstones) = (&frame.private, &frame.tombstones);
+
+            // Entries are kept if removing them would uncover a tombstone.
+            frame.map.retain(|key, value| {
+                private.as_ref().is_some_and(|private| private.contains(key))
+                    || tombstones.as_ref().is_some_and(|tombstones| tombstones.contains(key))
+                    || searched
+                        .iter()
+                        .chain(resumed)
+                        .take_while(|ctx| !ctx.hides(key))
+                        .find_map(|ctx| ctx.lookup(key, false)) != Some(value)
+            });
+
+            if frame.map.len() < before {
+                let mut counts = self.reads.take(frame.id);
+                let kept: HashSet<u64> = frame.map.keys().map(|key| frame.key_hash(key)).collect();
+
+                counts.retain(|key, _| kept.contains(key));
+                self.reads.insert(frame.id, counts);
+                pruned += before - frame.map.len();
+            }
         }
+
+        pruned
     }
 }
 
-impl<K, V, S> ContextManager<K, V, S> 
+
+impl<K, V, S, M> ContextManager<K, V, S, M>
 where K: Ord {
     /// Aggregates all contexts into a single map where keys have their most recent value.
     ///
+    /// Private entries are only taken from the local context.
+    ///
     /// # Example
     /// ```
     /// # use std::collections::HashMap;
@@ -764,8 +3321,17 @@ where K: Ord {
             if self.inner.is_empty() { break; }
 
             let next = self.inner.pop_back().unwrap();
+            let private: BTreeSet<&K> = if self.inner.is_empty() {
+                BTreeSet::new()
+            } else {
+                next.private.iter().flatten().collect()
+            };
+
+            for key in next.buried() {
+                map.remove(key);
+            }
 
-            map.extend(next);
+            map.extend(next.map.into_iter().filter(|(key, _)| !private.contains(key)));
         }
 
         map
@@ -773,6 +3339,8 @@ where K: Ord {
 
     /// Aggregates all contexts storing each key and its most recent value into `src`.
     ///
+    /// Private entries are only taken from the local context.
+    ///
     /// # Example
     /// ```
     /// # use std::collections::{BTreeMap, HashMap};
@@ -797,34 +3365,54 @@ where K: Ord {
             if self.inner.is_empty() { break; }
 
             let next = self.inner.pop_back().unwrap();
+            let private: BTreeSet<&K> = if self.inner.is_empty() {
+                BTreeSet::new()
+            } else {
+                next.private.iter().flatten().collect()
+            };
+
+            for key in next.buried() {
+                src.remove(key);
+            }
 
-            src.extend(next);
+            src.extend(next.map.into_iter().filter(|(key, _)| !private.contains(key)));
         }
     }
 }
-impl<K, V, S> Clone for ContextManager<K, V, S>
-where K: Clone, V: Clone, S: Clone{
-    fn clone(&self) -> Self { Self { inner: self.inner.clone() } }
+
+
+impl<K, V, S, M> Clone for ContextManager<K, V, S, M>
+where K: Clone, V: Clone, S: Clone, M: Clone {
+    fn clone(&self) -> Self {
+        let inner = self.inner.clone();
+        let reads = self.reads.remapped(self.inner.iter().zip(&inner).map(|(old, new)| {
+            (old.id, new.id)
+        }));
+
+        Self { reads, ..self.with_frames(inner) }
+    }
 }
 
 
-impl<K, V, S> Default for ContextManager<K, V, S> {
-    /// Creates an empty `ContextManager<K, V, S>`
-    fn default() -> Self { Self { inner: VecDeque::new() } }
+impl<K, V, S, M> Default for ContextManager<K, V, S, M> {
+    /// Creates an empty `ContextManager<K, V, S, M>`
+    fn default() -> Self { Self::from_frames(VecDeque::new()) }
 }
 
 
-impl <K, V, S> Extend<(K, V)> for ContextManager<K, V, S>
-where K: Hash + Eq, S: BuildHasher + Default {
+impl<K, V, S, M> Extend<(K, V)> for ContextManager<K, V, S, M>
+where K: Hash + Eq, S: BuildHasher + Default, M: Default {
     /// Adds key-value pairs from an iterator to the context manager.
     ///
     /// If the context manager is empty, a new `HashMap<K, V, S>` is created with the default
-    /// hasher.
+    /// hasher and default metadata.
     fn extend<I: IntoIterator<Item=(K, V)>>(&mut self, iter: I) {
         if self.inner.is_empty() {
-            self.inner.push_front(HashMap::from_iter(iter));
+            self.inner.push_front(Frame::from(HashMap::from_iter(iter)));
         } else {
-            self.inner[0].extend(iter);
+            for (key, value) in iter {
+                self.inner[0].insert(key, value);
+            }
         }
     }
 }
@@ -834,7 +3422,7 @@ impl<K, V, S> From<HashMap<K, V, S>> for ContextManager<K, V, S>
 where K: Hash + Eq, S: BuildHasher {
     /// Creates a new `ContextManager<K, V, S>` with `initial` as the first context.
     fn from(initial: HashMap<K, V, S>) -> Self {
-        Self { inner: VecDeque::from([initial]) }
+        Self::from_frames(VecDeque::from([Frame::from(initial)]))
     }
 }
 
@@ -846,7 +3434,7 @@ where K: Hash + Eq {
     ///
     /// Repeated keys will have all but one of the values dropped.
     fn from(initial: [(K, V); N]) -> Self {
-        Self { inner: VecDeque::from([HashMap::from(initial)]) }
+        Self::from_frames(VecDeque::from([Frame::from(HashMap::from(initial))]))
     }
 }
 
@@ -857,7 +3445,7 @@ where K: Hash + Eq, S: BuildHasher {
     ///
     /// Precedence proceeds from the first context toward the last.
     fn from(initial: [HashMap<K, V, S>; N]) -> Self {
-        Self { inner: VecDeque::from(initial) }
+        Self::from_frames(initial.into_iter().map(Frame::from).collect())
     }
 }
 
@@ -869,7 +3457,7 @@ where K: Hash + Eq, S: BuildHasher + Default {
     ///
     /// Repeated keys will have all but one of the values dropped.
     fn from_iter<I: IntoIterator<Item=(K, V)>>(initial: I) -> Self {
-        Self { inner: VecDeque::from([HashMap::from_iter(initial)]) }
+        Self::from_frames(VecDeque::from([Frame::from(HashMap::from_iter(initial))]))
     }
 }
 
@@ -880,12 +3468,12 @@ where K: Hash + Eq, S: BuildHasher {
     ///
     /// Precedence proceeds from the first context toward the last.
     fn from_iter<I: IntoIterator<Item=HashMap<K, V, S>>>(iter: I) -> Self {
-        Self { inner: VecDeque::from_iter(iter) }
+        Self::from_frames(iter.into_iter().map(Frame::from).collect())
     }
 }
 
 
-impl<K, Q, V, S> Index<&Q> for ContextManager<K, V, S>
+impl<K, Q, V, S, M> Index<&Q> for ContextManager<K, V, S, M>
 where K: Hash + Eq + Borrow<Q>, Q: ?Sized + Hash + Eq, S: BuildHasher {
     type Output = V;
 
@@ -893,18 +3481,18 @@ where K: Hash + Eq + Borrow<Q>, Q: ?Sized + Hash + Eq, S: BuildHasher {
     ///
     /// Panics if the context manager is empty, or `key` is not found in any contexts.
     fn index(&self, key: &Q) -> &V {
-        self.inner.iter().find_map(|ctx| ctx.get(key)).expect("key not found")
+        self.get(key).expect("key not found")
     }
 }
 
 
-impl<K, V, S> PartialEq for ContextManager<K, V, S>
-where K: Hash + Eq, V: PartialEq, S: BuildHasher {
-    fn eq(&self, other: &ContextManager<K, V, S>) -> bool {
-        self.inner.eq(&other.inner)
+impl<K, V, S, M> PartialEq for ContextManager<K, V, S, M>
+where K: Hash + Eq, V: PartialEq, S: BuildHasher, M: PartialEq {
+    fn eq(&self, other: &ContextManager<K, V, S, M>) -> bool {
+        self.global_depth == other.global_depth && self.inner.eq(&other.inner)
     }
 }
 
 
-impl<K, V, S> Eq for ContextManager<K, V, S>
-where K: Hash + Eq, V: Eq, S: BuildHasher {}
\ No newline at end of file
+impl<K, V, S, M> Eq for ContextManager<K, V, S, M>
+where K: Hash + Eq, V: Eq, S: BuildHasher, M: Eq {}
\ No newline at end of file
@@ -0,0 +1,305 @@
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hash, RandomState};
+
+use crate::ContextDiff;
+
+/// A set of changes to the bindings visible from a [ContextManager](crate::ContextManager).
+///
+/// A patch holds at most one change per key. Sets always bind the key, private sets bind it only
+/// for the local context, updates only bind keys that are already visible, and removals hide
+/// keys with a tombstone. Patches are applied with
+/// [ContextManager::apply_patch](crate::ContextManager::apply_patch) or
+/// [ContextManager::push_patch](crate::ContextManager::push_patch), which return the patch that
+/// reverts them.
+#[derive(Clone, Debug)]
+pub struct ContextPatch<K, V, S = RandomState> {
+    pub(crate) sets: HashMap<K, V, S>,
+    pub(crate) private: HashMap<K, V, S>,
+    pub(crate) updates: HashMap<K, V, S>,
+    pub(crate) removals: HashSet<K, S>
+}
+
+
+impl<K, V> ContextPatch<K, V, RandomState> {
+    /// Creates an empty patch.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextPatch;
+    /// let patch = ContextPatch::<&str, i32>::new();
+    ///
+    /// assert!(patch.is_empty());
+    /// ```
+    pub fn new() -> Self { Self::default() }
+}
+
+
+impl<K, V, S> ContextPatch<K, V, S> {
+    /// Whether the patch holds no changes.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextPatch;
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// assert!(patch.is_empty());
+    ///
+    /// patch.set("w", 1);
+    ///
+    /// assert!(!patch.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+            && self.private.is_empty()
+            && self.updates.is_empty()
+            && self.removals.is_empty()
+    }
+
+    /// Returns the number of changes in the patch.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextPatch;
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// patch.set("w", 1);
+    /// patch.set_private("x", 2);
+    /// patch.update("y", 3);
+    /// patch.remove("z");
+    ///
+    /// assert_eq!(patch.len(), 4);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.sets.len() + self.private.len() + self.updates.len() + self.removals.len()
+    }
+
+    /// Returns the bindings the patch sets privately.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextPatch;
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// patch.set_private("w", 1);
+    ///
+    /// assert_eq!(patch.private(), &HashMap::from([("w", 1)]));
+    /// ```
+    pub fn private(&self) -> &HashMap<K, V, S> { &self.private }
+
+    /// Returns the keys the patch removes.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashSet;
+    /// # use contexts::ContextPatch;
+    /// let mut patch = ContextPatch::<&str, i32>::new();
+    ///
+    /// patch.remove("w");
+    ///
+    /// assert_eq!(patch.removals(), &HashSet::from(["w"]));
+    /// ```
+    pub fn removals(&self) -> &HashSet<K, S> { &self.removals }
+
+    /// Returns the bindings the patch sets.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextPatch;
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// patch.set("w", 1);
+    ///
+    /// assert_eq!(patch.sets(), &HashMap::from([("w", 1)]));
+    /// ```
+    pub fn sets(&self) -> &HashMap<K, V, S> { &self.sets }
+
+    /// Returns the bindings the patch updates.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextPatch;
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// patch.update("w", 1);
+    ///
+    /// assert_eq!(patch.updates(), &HashMap::from([("w", 1)]));
+    /// ```
+    pub fn updates(&self) -> &HashMap<K, V, S> { &self.updates }
+}
+
+
+impl<K, V, S> ContextPatch<K, V, S>
+where K: Hash + Eq, S: BuildHasher {
+    /// Creates an empty patch using `hasher` to hash keys.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::hash::RandomState;
+    /// # use contexts::ContextPatch;
+    /// let mut patch = ContextPatch::with_hasher(RandomState::new());
+    ///
+    /// patch.set("w", 1);
+    ///
+    /// assert_eq!(patch.len(), 1);
+    /// ```
+    pub fn with_hasher(hasher: S) -> Self
+    where S: Clone {
+        Self {
+            sets: HashMap::with_hasher(hasher.clone()),
+            private: HashMap::with_hasher(hasher.clone()),
+            updates: HashMap::with_hasher(hasher.clone()),
+            removals: HashSet::with_hasher(hasher)
+        }
+    }
+
+    /// Removes any change to `key` from the patch.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextPatch;
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// patch.set("w", 1);
+    /// patch.discard("w");
+    ///
+    /// assert!(patch.is_empty());
+    /// ```
+    pub fn discard<Q>(&mut self, key: &Q)
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.sets.remove(key);
+        self.private.remove(key);
+        self.updates.remove(key);
+        self.removals.remove(key);
+    }
+
+    /// Records the removal of `key`, replacing any other change to it.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextPatch;
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// patch.set("w", 1);
+    /// patch.remove("w");
+    ///
+    /// assert!(patch.sets().is_empty());
+    /// assert!(patch.removals().contains("w"));
+    /// ```
+    pub fn remove(&mut self, key: K) {
+        self.discard(&key);
+        self.removals.insert(key);
+    }
+
+    /// Records binding `key` to `value`, replacing any other change to it.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextPatch;
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// patch.remove("w");
+    /// patch.set("w", 1);
+    ///
+    /// assert!(patch.removals().is_empty());
+    /// assert_eq!(patch.sets().get("w"), Some(&1));
+    /// ```
+    pub fn set(&mut self, key: K, value: V) {
+        self.discard(&key);
+        self.sets.insert(key, value);
+    }
+
+    /// Records binding `key` to `value` only for the local context, as
+    /// [ContextManager::insert_private](crate::ContextManager::insert_private) does, replacing any
+    /// other change to it.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextPatch;
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// patch.set("w", 1);
+    /// patch.set_private("w", 2);
+    ///
+    /// assert!(patch.sets().is_empty());
+    /// assert_eq!(patch.private().get("w"), Some(&2));
+    /// ```
+    pub fn set_private(&mut self, key: K, value: V) {
+        self.discard(&key);
+        self.private.insert(key, value);
+    }
+
+    /// Records binding `key` to `value` if it is visible when the patch is applied, replacing any
+    /// other change to it.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextPatch;
+    /// let mut patch = ContextPatch::new();
+    ///
+    /// patch.set("w", 1);
+    /// patch.update("w", 2);
+    ///
+    /// assert!(patch.sets().is_empty());
+    /// assert_eq!(patch.updates().get("w"), Some(&2));
+    /// ```
+    pub fn update(&mut self, key: K, value: V) {
+        self.discard(&key);
+        self.updates.insert(key, value);
+    }
+}
+
+
+impl<K, V, S> Default for ContextPatch<K, V, S>
+where S: Default {
+    fn default() -> Self {
+        Self {
+            sets: HashMap::default(),
+            private: HashMap::default(),
+            updates: HashMap::default(),
+            removals: HashSet::default()
+        }
+    }
+}
+
+
+impl<K, V> From<ContextDiff<'_, K, V>> for ContextPatch<K, V, RandomState>
+where K: Hash + Eq + Clone, V: Clone {
+    /// Creates a patch that turns the old view of `diff` into the new one, setting added keys,
+    /// updating changed keys and removing removed keys.
+    fn from(diff: ContextDiff<'_, K, V>) -> Self {
+        let mut patch = Self::new();
+
+        for (key, value) in diff.added {
+            patch.set(key.clone(), value.clone());
+        }
+
+        for (key, (_, value)) in diff.changed {
+            patch.update(key.clone(), value.clone());
+        }
+
+        for key in diff.removed.into_keys() {
+            patch.remove(key.clone());
+        }
+
+        patch
+    }
+}
+
+
+impl<K, V, S> PartialEq for ContextPatch<K, V, S>
+where K: Hash + Eq, V: PartialEq, S: BuildHasher {
+    fn eq(&self, other: &Self) -> bool {
+        self.sets == other.sets
+            && self.private == other.private
+            && self.updates == other.updates
+            && self.removals == other.removals
+    }
+}
+
+
+impl<K, V, S> Eq for ContextPatch<K, V, S>
+where K: Hash + Eq, V: Eq, S: BuildHasher {}
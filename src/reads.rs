@@ -0,0 +1,95 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Identifies a context for as long as it exists, including clones of it as new contexts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct FrameId(u64);
+
+
+impl FrameId {
+    /// Returns an id that no other context was given.
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+
+/// The number of counted reads of each entry of a manager, keyed by the id of its context and
+/// the hash of its key.
+///
+/// Keys are identified by their hash so that reads can be counted without an owned key. Keys of
+/// one context whose hashes collide share a count.
+#[derive(Debug, Default)]
+pub(crate) struct ReadCounts {
+    counts: Mutex<HashMap<FrameId, HashMap<u64, usize>>>
+}
+
+
+impl ReadCounts {
+    /// Returns the number of counted reads of the entry for the key hashed to `key` in `frame`.
+    pub(crate) fn count(&self, frame: FrameId, key: u64) -> usize {
+        self.lock().get(&frame).and_then(|counts| counts.get(&key).copied()).unwrap_or(0)
+    }
+
+    /// Forgets the counted reads of the entry for the key hashed to `key` in `frame`.
+    pub(crate) fn forget(&mut self, frame: FrameId, key: u64) {
+        if let Some(counts) = self.counts_mut().get_mut(&frame) {
+            counts.remove(&key);
+        }
+    }
+
+    /// Stores `counts` as the counted reads of the entries of `frame`.
+    pub(crate) fn insert(&mut self, frame: FrameId, counts: HashMap<u64, usize>) {
+        if !counts.is_empty() {
+            self.counts_mut().insert(frame, counts);
+        }
+    }
+
+    /// Counts a read of the entry for the key hashed to `key` in `frame`, first forgetting the
+    /// counts of contexts other than the `live` ones if they could be stale.
+    pub(crate) fn record<I>(&self, frame: FrameId, key: u64, live: I)
+    where I: ExactSizeIterator<Item = FrameId> {
+        let mut counts = self.lock();
+
+        // Only the live contexts other than `frame` can have counts when it has none, so more
+        // counts than that means some belong to removed contexts.
+        if !counts.contains_key(&frame) && counts.len() >= live.len() {
+            let live: HashSet<FrameId> = live.collect();
+
+            counts.retain(|frame, _| live.contains(frame));
+        }
+
+        *counts.entry(frame).or_default().entry(key).or_default() += 1;
+    }
+
+    /// Returns a copy of the counts with the counts of each context moved to the id it is paired
+    /// with in `ids`, leaving out contexts that are not in `ids`.
+    pub(crate) fn remapped<I>(&self, ids: I) -> Self
+    where I: IntoIterator<Item = (FrameId, FrameId)> {
+        let counts = self.lock();
+        let remapped = ids
+            .into_iter()
+            .filter_map(|(old, new)| counts.get(&old).map(|counts| (new, counts.clone())))
+            .collect();
+
+        Self { counts: Mutex::new(remapped) }
+    }
+
+    /// Removes and returns the counted reads of the entries of `frame`.
+    pub(crate) fn take(&mut self, frame: FrameId) -> HashMap<u64, usize> {
+        self.counts_mut().remove(&frame).unwrap_or_default()
+    }
+
+    /// Returns the counts without locking them.
+    fn counts_mut(&mut self) -> &mut HashMap<FrameId, HashMap<u64, usize>> {
+        self.counts.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the counts, recovering them if a panic poisoned the lock.
+    fn lock(&self) -> MutexGuard<'_, HashMap<FrameId, HashMap<u64, usize>>> {
+        self.counts.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,309 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::ops::Index;
+
+use crate::ContextManager;
+
+/// A scope that owns its local contexts and falls back to a borrowed parent for lookups.
+///
+/// Insertions and removals only affect the scope's own contexts, so a parent can be extended
+/// without being cloned or mutably borrowed. Child scopes are created with
+/// [ContextManager::child] or [ChildScope::child].
+#[derive(Debug)]
+pub struct ChildScope<'a, K, V, S = RandomState, M = ()> {
+    local: ContextManager<K, V, S, M>,
+    parent: Parent<'a, K, V, S, M>
+}
+
+
+/// The borrowed parent of a [ChildScope].
+#[derive(Debug)]
+enum Parent<'a, K, V, S, M> {
+    Manager(&'a ContextManager<K, V, S, M>),
+    Scope(&'a ChildScope<'a, K, V, S, M>)
+}
+
+
+impl<K, V, S, M> Clone for Parent<'_, K, V, S, M> {
+    fn clone(&self) -> Self { *self }
+}
+
+
+impl<K, V, S, M> Copy for Parent<'_, K, V, S, M> {}
+
+
+impl<K, V, S, M> Parent<'_, K, V, S, M>
+where K: Hash + Eq, S: BuildHasher {
+    /// Returns a reference to the value associated with `key` as seen from a child scope, which
+    /// excludes the private entries of the parent's local context.
+    fn get<Q>(&self, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        match self {
+            Parent::Manager(manager) => manager.get_inherited(key),
+            Parent::Scope(scope) => scope.get_inherited(key)
+        }
+    }
+}
+
+
+impl<K, V, S, M> ChildScope<'_, K, V, S, M> {
+    /// Whether the scope owns no contexts.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let mut scope = manager.child();
+    ///
+    /// assert!(!scope.is_empty());
+    ///
+    /// scope.pop();
+    ///
+    /// assert!(scope.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool { self.local.is_empty() }
+
+    /// Returns the number of contexts owned by the scope.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let mut scope = manager.child();
+    ///
+    /// assert_eq!(scope.len(), 1);
+    ///
+    /// scope.push(HashMap::from([("x", 2)]));
+    ///
+    /// assert_eq!(scope.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize { self.local.len() }
+
+    /// Consumes the scope, returning a manager holding only the contexts it owns.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let mut scope = manager.child();
+    ///
+    /// scope.insert("x", 2);
+    ///
+    /// let owned = scope.into_local();
+    ///
+    /// assert_eq!(owned.len(), 1);
+    /// assert_eq!(owned.get("w"), None);
+    /// assert_eq!(&owned["x"], &2);
+    /// ```
+    pub fn into_local(self) -> ContextManager<K, V, S, M> { self.local }
+
+    /// Removes the local context if the scope owns one.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let mut scope = manager.child();
+    ///
+    /// scope.insert("w", 2);
+    ///
+    /// assert_eq!(scope.pop().unwrap().get("w"), Some(&2));
+    /// assert_eq!(scope.pop(), None);
+    /// assert_eq!(&scope["w"], &1);
+    /// ```
+    pub fn pop(&mut self) -> Option<HashMap<K, V, S>> { self.local.pop() }
+
+    /// Adds a new local context to the scope.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let mut scope = manager.child();
+    ///
+    /// scope.push(HashMap::from([("w", 2)]));
+    ///
+    /// assert_eq!(&scope["w"], &2);
+    /// ```
+    pub fn push(&mut self, context: HashMap<K, V, S>)
+    where M: Default {
+        self.local.push(context)
+    }
+}
+
+
+impl<'a, K, V, S, M> ChildScope<'a, K, V, S, M>
+where K: Hash + Eq, S: BuildHasher {
+    /// Creates a scope with an empty local context that falls back to `manager`.
+    pub(crate) fn with_manager(manager: &'a ContextManager<K, V, S, M>) -> Self
+    where S: Default, M: Default {
+        Self::with_parent(Parent::Manager(manager))
+    }
+
+    fn with_parent(parent: Parent<'a, K, V, S, M>) -> Self
+    where S: Default, M: Default {
+        let mut local = ContextManager::default();
+
+        local.push(HashMap::default());
+
+        Self { local, parent }
+    }
+
+    /// Creates a scope with an empty local context that falls back to this scope.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let mut scope = manager.child();
+    ///
+    /// scope.insert("x", 2);
+    ///
+    /// let mut nested = scope.child();
+    ///
+    /// nested.insert("y", 3);
+    ///
+    /// assert_eq!(&nested["w"], &1);
+    /// assert_eq!(&nested["x"], &2);
+    /// assert_eq!(&nested["y"], &3);
+    /// assert_eq!(scope.get("y"), None);
+    /// ```
+    pub fn child(&self) -> ChildScope<'_, K, V, S, M>
+    where S: Default, M: Default {
+        ChildScope::with_parent(Parent::Scope(self))
+    }
+
+    /// Whether a key is present in the scope or its parents.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let mut scope = manager.child();
+    ///
+    /// scope.insert("x", 2);
+    ///
+    /// assert!(scope.contains_key("w"));
+    /// assert!(scope.contains_key("x"));
+    /// assert!(!manager.contains_key("x"));
+    /// ```
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.get(key).is_some()
+    }
+
+    /// Returns a reference to the value associated with `key`, searching the scope before its
+    /// parents.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1), ("x", 2)]);
+    /// let mut scope = manager.child();
+    ///
+    /// scope.insert("x", 3);
+    ///
+    /// assert_eq!(scope.get("w"), Some(&1));
+    /// assert_eq!(scope.get("x"), Some(&3));
+    /// assert_eq!(scope.get("y"), None);
+    /// ```
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.local.get(key).or_else(|| self.parent.get(key))
+    }
+
+    /// Returns a reference to the value associated with `key` as seen from a child scope.
+    fn get_inherited<Q>(&self, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.local.get_inherited(key).or_else(|| self.parent.get(key))
+    }
+
+    /// Returns a reference to the value associated with `key` in the local context.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let mut scope = manager.child();
+    ///
+    /// scope.insert("x", 2);
+    ///
+    /// assert_eq!(scope.get_local("w"), None);
+    /// assert_eq!(scope.get_local("x"), Some(&2));
+    /// ```
+    pub fn get_local<Q>(&self, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.local.get_local(key)
+    }
+
+    /// Returns a mutable reference to the value associated with `key` in the contexts owned by
+    /// the scope.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let mut scope = manager.child();
+    ///
+    /// scope.insert("x", 2);
+    ///
+    /// if let Some(x) = scope.get_mut("x") {
+    ///     *x = 3;
+    /// }
+    ///
+    /// assert_eq!(&scope["x"], &3);
+    /// assert_eq!(scope.get_mut("w"), None);
+    /// ```
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.local.get_mut(key)
+    }
+
+    /// Associates `value` with `key` in the local context if the scope owns one.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let mut scope = manager.child();
+    ///
+    /// assert_eq!(scope.insert("w", 2), None);
+    /// assert_eq!(scope.insert("w", 3), Some(2));
+    /// assert_eq!(&scope["w"], &3);
+    /// assert_eq!(&manager["w"], &1);
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> { self.local.insert(key, value) }
+
+    /// Removes `key` from the local context if the scope owns one.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    /// let mut scope = manager.child();
+    ///
+    /// scope.insert("w", 2);
+    ///
+    /// assert_eq!(scope.remove("w"), Some(2));
+    /// assert_eq!(scope.remove("w"), None);
+    /// assert_eq!(&scope["w"], &1);
+    /// ```
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.local.remove(key)
+    }
+}
+
+
+impl<K, Q, V, S, M> Index<&Q> for ChildScope<'_, K, V, S, M>
+where K: Hash + Eq + Borrow<Q>, Q: ?Sized + Hash + Eq, S: BuildHasher {
+    type Output = V;
+
+    /// Returns a reference to the value associated with `key`.
+    ///
+    /// Panics if `key` is not found in the scope or any of its parents.
+    fn index(&self, key: &Q) -> &V { self.get(key).expect("key not found") }
+}
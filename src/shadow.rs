@@ -0,0 +1,25 @@
+/// Whether [ContextManager::insert_checked](crate::ContextManager::insert_checked) may shadow
+/// bindings visible from outer contexts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ShadowPolicy {
+    /// Inserts the binding and reports the shadowed one.
+    #[default]
+    Allow,
+    /// Leaves the manager unchanged and hands the value back.
+    Reject
+}
+
+
+/// The effect of [ContextManager::insert_checked](crate::ContextManager::insert_checked).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InsertOutcome<V> {
+    /// The key was not visible from the local context.
+    New,
+    /// The key was bound in the local context, which held the contained value.
+    Replaced(V),
+    /// The new binding shadows the one in the context at `depth`.
+    Shadowed { depth: usize },
+    /// The new binding would have shadowed the one in the context at `depth`, so `value` was
+    /// not inserted.
+    Rejected { depth: usize, value: V }
+}
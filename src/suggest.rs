@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::fmt;
+
+/// The reasons a lookup through [ContextManager::try_get](crate::ContextManager::try_get) can
+/// fail.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LookupError<'a, K> {
+    /// The key is not visible from the local context. `suggestions` holds the closest visible
+    /// keys, best match first.
+    NotFound { suggestions: Vec<&'a K> }
+}
+
+
+impl<K> fmt::Display for LookupError<'_, K>
+where K: AsRef<str> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LookupError::NotFound { suggestions } => {
+                write!(f, "key not found")?;
+
+                if let Some((last, rest)) = suggestions.split_last() {
+                    write!(f, ", did you mean ")?;
+
+                    for (index, key) in rest.iter().enumerate() {
+                        let separator = if index == 0 { "" } else { ", " };
+
+                        write!(f, "{separator}`{}`", key.as_ref())?;
+                    }
+
+                    if !rest.is_empty() {
+                        write!(f, " or ")?;
+                    }
+
+                    write!(f, "`{}`?", last.as_ref())?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+
+impl<K> Error for LookupError<'_, K>
+where K: AsRef<str> + fmt::Debug {}
+
+
+/// Returns the number of single character insertions, deletions and substitutions needed to
+/// turn `a` into `b`.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, x) in a.chars().enumerate() {
+        current[0] = i + 1;
+
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != *y);
+
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
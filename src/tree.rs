@@ -0,0 +1,381 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, RandomState};
+
+use crate::{ContextManager, Frame};
+
+/// Identifies a scope within a [ScopeTree] or a [ScopeGraph](crate::ScopeGraph).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ScopeId(pub(crate) usize);
+
+
+impl ScopeId {
+    /// Returns the position of the scope in the order scopes were added, starting at `0`.
+    pub fn index(&self) -> usize { self.0 }
+}
+
+
+/// An arena of scopes linked to their parents, where every scope stays queryable after it is
+/// left.
+///
+/// Each scope is a context with its own metadata. Resolving a key in a scope searches the scope
+/// and then its ancestors, just as a [ContextManager] searches from its local context outward.
+///
+/// Scope identifiers are only meaningful for the tree that created them. Methods taking a
+/// [ScopeId] panic if the scope does not exist in the tree.
+#[derive(Clone, Debug)]
+pub struct ScopeTree<K, V, S = RandomState, M = ()> {
+    scopes: Vec<Scope<K, V, S, M>>
+}
+
+
+/// A scope and the link to its parent.
+#[derive(Clone, Debug)]
+struct Scope<K, V, S, M> {
+    frame: Frame<K, V, S, M>,
+    parent: Option<ScopeId>
+}
+
+
+impl<K, V> ScopeTree<K, V, RandomState>
+where K: Hash + Eq {
+    /// Creates a tree holding an empty root scope.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::new();
+    /// let root = tree.root();
+    ///
+    /// tree.insert(root, "x", 1);
+    ///
+    /// assert_eq!(tree.resolve(root, "x"), Some(&1));
+    /// assert_eq!(tree.len(), 1);
+    /// ```
+    pub fn new() -> Self { Self::default() }
+}
+
+
+impl<K, V, S, M> ScopeTree<K, V, S, M> {
+    /// Returns an iterator over `scope` and its ancestors, ending with the root.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::<&str, i32>::new();
+    /// let root = tree.root();
+    /// let outer = tree.enter(root);
+    /// let inner = tree.enter(outer);
+    ///
+    /// assert_eq!(tree.ancestors(inner).collect::<Vec<_>>(), vec![inner, outer, root]);
+    /// ```
+    pub fn ancestors(&self, scope: ScopeId) -> impl Iterator<Item = ScopeId> + '_ {
+        assert!(scope.0 < self.scopes.len(), "scope not found");
+
+        std::iter::successors(Some(scope), |id| self.scopes[id.0].parent)
+    }
+
+    /// Returns a reference to the context of `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::new();
+    /// let root = tree.root();
+    ///
+    /// tree.insert(root, "x", 1);
+    ///
+    /// assert_eq!(tree.context(root), &HashMap::from([("x", 1)]));
+    /// ```
+    pub fn context(&self, scope: ScopeId) -> &HashMap<K, V, S> { &self.scopes[scope.0].frame.map }
+
+    /// Returns a mutable reference to the context of `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::new();
+    /// let root = tree.root();
+    ///
+    /// tree.context_mut(root).insert("x", 1);
+    ///
+    /// assert_eq!(tree.resolve(root, "x"), Some(&1));
+    /// ```
+    pub fn context_mut(&mut self, scope: ScopeId) -> &mut HashMap<K, V, S> {
+        &mut self.scopes[scope.0].frame.map
+    }
+
+    /// Returns the number of scopes in the tree, including the root.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::<&str, i32>::new();
+    /// let root = tree.root();
+    ///
+    /// tree.enter(root);
+    /// tree.enter(root);
+    ///
+    /// assert_eq!(tree.len(), 3);
+    /// ```
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize { self.scopes.len() }
+
+    /// Returns a reference to the metadata of `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::hash::RandomState;
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::<&str, i32, RandomState, &str>::default();
+    /// let root = tree.root();
+    /// let block = tree.enter_with_metadata(root, "block");
+    ///
+    /// assert_eq!(tree.metadata(root), &"");
+    /// assert_eq!(tree.metadata(block), &"block");
+    /// ```
+    pub fn metadata(&self, scope: ScopeId) -> &M { &self.scopes[scope.0].frame.meta }
+
+    /// Returns a mutable reference to the metadata of `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::hash::RandomState;
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::<&str, i32, RandomState, usize>::default();
+    /// let root = tree.root();
+    ///
+    /// *tree.metadata_mut(root) = 3;
+    ///
+    /// assert_eq!(tree.metadata(root), &3);
+    /// ```
+    pub fn metadata_mut(&mut self, scope: ScopeId) -> &mut M {
+        &mut self.scopes[scope.0].frame.meta
+    }
+
+    /// Returns the parent of `scope`, or `None` for the root.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::<&str, i32>::new();
+    /// let root = tree.root();
+    /// let child = tree.enter(root);
+    ///
+    /// assert_eq!(tree.parent(child), Some(root));
+    /// assert_eq!(tree.parent(root), None);
+    /// ```
+    pub fn parent(&self, scope: ScopeId) -> Option<ScopeId> { self.scopes[scope.0].parent }
+
+    /// Returns the root scope of the tree.
+    pub fn root(&self) -> ScopeId { ScopeId(0) }
+}
+
+
+impl<K, V, S, M> ScopeTree<K, V, S, M>
+where K: Hash + Eq, S: BuildHasher {
+    /// Whether a key is present in `scope` or any of its ancestors.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::new();
+    /// let root = tree.root();
+    /// let child = tree.enter(root);
+    ///
+    /// tree.insert(child, "x", 1);
+    ///
+    /// assert!(tree.contains_key(child, "x"));
+    /// assert!(!tree.contains_key(root, "x"));
+    /// ```
+    pub fn contains_key<Q>(&self, scope: ScopeId, key: &Q) -> bool
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.resolve_scope(scope, key).is_some()
+    }
+
+    /// Adds a new empty scope whose parent is `parent`, returning its identifier.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::new();
+    /// let root = tree.root();
+    ///
+    /// tree.insert(root, "x", 1);
+    ///
+    /// let child = tree.enter(root);
+    ///
+    /// tree.insert(child, "x", 2);
+    ///
+    /// assert_eq!(tree.resolve(child, "x"), Some(&2));
+    /// assert_eq!(tree.resolve(root, "x"), Some(&1));
+    /// ```
+    pub fn enter(&mut self, parent: ScopeId) -> ScopeId
+    where S: Default, M: Default {
+        self.enter_with_metadata(parent, M::default())
+    }
+
+    /// Adds a new empty scope with the given metadata whose parent is `parent`, returning its
+    /// identifier.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::hash::RandomState;
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::<&str, i32, RandomState, &str>::default();
+    /// let root = tree.root();
+    /// let function = tree.enter_with_metadata(root, "function");
+    ///
+    /// assert_eq!(tree.metadata(function), &"function");
+    /// assert_eq!(tree.parent(function), Some(root));
+    /// ```
+    pub fn enter_with_metadata(&mut self, parent: ScopeId, metadata: M) -> ScopeId
+    where S: Default {
+        assert!(parent.0 < self.scopes.len(), "scope not found");
+
+        self.scopes.push(Scope {
+            frame: Frame::new(HashMap::default(), metadata),
+            parent: Some(parent)
+        });
+
+        ScopeId(self.scopes.len() - 1)
+    }
+
+    /// Returns a reference to the value associated with `key` in `scope` alone.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::new();
+    /// let root = tree.root();
+    /// let child = tree.enter(root);
+    ///
+    /// tree.insert(root, "x", 1);
+    ///
+    /// assert_eq!(tree.get_local(root, "x"), Some(&1));
+    /// assert_eq!(tree.get_local(child, "x"), None);
+    /// ```
+    pub fn get_local<Q>(&self, scope: ScopeId, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.scopes[scope.0].frame.map.get(key)
+    }
+
+    /// Associates `value` with `key` in `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::new();
+    /// let root = tree.root();
+    ///
+    /// assert_eq!(tree.insert(root, "x", 1), None);
+    /// assert_eq!(tree.insert(root, "x", 2), Some(1));
+    /// ```
+    pub fn insert(&mut self, scope: ScopeId, key: K, value: V) -> Option<V> {
+        self.scopes[scope.0].frame.map.insert(key, value)
+    }
+
+    /// Removes `key` from `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::new();
+    /// let root = tree.root();
+    /// let child = tree.enter(root);
+    ///
+    /// tree.insert(root, "x", 1);
+    /// tree.insert(child, "x", 2);
+    ///
+    /// assert_eq!(tree.remove(child, "x"), Some(2));
+    /// assert_eq!(tree.remove(child, "x"), None);
+    /// assert_eq!(tree.resolve(child, "x"), Some(&1));
+    /// ```
+    pub fn remove<Q>(&mut self, scope: ScopeId, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.scopes[scope.0].frame.map.remove(key)
+    }
+
+    /// Returns a reference to the value associated with `key`, searching `scope` and then its
+    /// ancestors.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::new();
+    /// let root = tree.root();
+    /// let left = tree.enter(root);
+    /// let right = tree.enter(root);
+    ///
+    /// tree.insert(root, "x", 1);
+    /// tree.insert(left, "x", 2);
+    ///
+    /// assert_eq!(tree.resolve(left, "x"), Some(&2));
+    /// assert_eq!(tree.resolve(right, "x"), Some(&1));
+    /// assert_eq!(tree.resolve(right, "y"), None);
+    /// ```
+    pub fn resolve<Q>(&self, scope: ScopeId, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.ancestors(scope).find_map(|id| self.scopes[id.0].frame.map.get(key))
+    }
+
+    /// Returns the scope that defines the value `key` resolves to from `scope`.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::new();
+    /// let root = tree.root();
+    /// let child = tree.enter(root);
+    ///
+    /// tree.insert(root, "x", 1);
+    ///
+    /// assert_eq!(tree.resolve_scope(child, "x"), Some(root));
+    /// assert_eq!(tree.resolve_scope(child, "y"), None);
+    /// ```
+    pub fn resolve_scope<Q>(&self, scope: ScopeId, key: &Q) -> Option<ScopeId>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.ancestors(scope).find(|id| self.scopes[id.0].frame.map.contains_key(key))
+    }
+}
+
+
+impl<K, V, S, M> ScopeTree<K, V, S, M>
+where K: Hash + Eq + Clone, V: Clone, S: BuildHasher + Clone, M: Clone {
+    /// Creates a context manager with clones of `scope` and its ancestors, with `scope` as the
+    /// local context and the root as the global context.
+    ///
+    /// # Example
+    /// ```
+    /// # use contexts::ScopeTree;
+    /// let mut tree = ScopeTree::new();
+    /// let root = tree.root();
+    /// let child = tree.enter(root);
+    ///
+    /// tree.insert(root, "x", 1);
+    /// tree.insert(child, "y", 2);
+    ///
+    /// let manager = tree.to_manager(child);
+    ///
+    /// assert_eq!(manager.len(), 2);
+    /// assert_eq!(manager.get_local("y"), Some(&2));
+    /// assert_eq!(manager.get_global("x"), Some(&1));
+    /// ```
+    pub fn to_manager(&self, scope: ScopeId) -> ContextManager<K, V, S, M> {
+        ContextManager::from_frames(
+            self.ancestors(scope).map(|id| self.scopes[id.0].frame.clone()).collect()
+        )
+    }
+}
+
+
+impl<K, V, S, M> Default for ScopeTree<K, V, S, M>
+where S: Default, M: Default {
+    /// Creates a `ScopeTree<K, V, S, M>` holding an empty root scope.
+    fn default() -> Self {
+        let root = Scope { frame: Frame::new(HashMap::default(), M::default()), parent: None };
+
+        Self { scopes: vec![root] }
+    }
+}
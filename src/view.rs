@@ -0,0 +1,190 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::ops::Index;
+
+use crate::Frame;
+
+/// A borrowed, read-only view into a range of the contexts of a
+/// [ContextManager](crate::ContextManager).
+///
+/// Lookups behave as they do on the manager, but only search the contexts in the view. Barriers
+/// are not considered, so every context in the view is searched until one that removed the key,
+/// although the view from [ContextManager::split_local_mut](crate::ContextManager::split_local_mut)
+/// already leaves out the contexts hidden by barriers. Private entries are only visible if the
+/// view starts at the local context of the manager.
+#[derive(Debug)]
+pub struct ContextView<'a, K, V, S = RandomState, M = ()> {
+    front: &'a [Frame<K, V, S, M>],
+    back: &'a [Frame<K, V, S, M>],
+    local: bool
+}
+
+
+impl<'a, K, V, S, M> ContextView<'a, K, V, S, M> {
+    /// Creates a view over `front` followed by `back`, given whether the first of them is the
+    /// local context.
+    pub(crate) fn new(
+        front: &'a [Frame<K, V, S, M>],
+        back: &'a [Frame<K, V, S, M>],
+        local: bool
+    ) -> Self {
+        Self { front, back, local }
+    }
+
+    /// Whether the view contains no contexts.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let manager = ContextManager::from([("w", 1)]);
+    ///
+    /// assert!(!manager.view(..).is_empty());
+    /// assert!(manager.view(1..).is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool { self.front.is_empty() && self.back.is_empty() }
+
+    /// Returns an iterator over the contexts in the view, ordered by precedence.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("x", 2)]));
+    /// manager.push(HashMap::from([("y", 3)]));
+    ///
+    /// let view = manager.view(..2);
+    /// let mut contexts = view.iter();
+    ///
+    /// assert_eq!(contexts.next(), Some(&HashMap::from([("y", 3)])));
+    /// assert_eq!(contexts.next(), Some(&HashMap::from([("x", 2)])));
+    /// assert_eq!(contexts.next(), None);
+    /// ```
+    pub fn iter(&self)
+        -> impl DoubleEndedIterator<Item = &'a HashMap<K, V, S>> + use<'a, K, V, S, M> {
+        self.frames().map(|frame| &frame.map)
+    }
+
+    /// Returns the number of contexts in the view.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("x", 2)]));
+    /// manager.push(HashMap::from([("y", 3)]));
+    ///
+    /// assert_eq!(manager.view(..).len(), 3);
+    /// assert_eq!(manager.view(1..2).len(), 1);
+    /// ```
+    pub fn len(&self) -> usize { self.front.len() + self.back.len() }
+
+    /// Returns an iterator over the frames in the view, ordered by precedence.
+    pub(crate) fn frames(&self)
+        -> impl DoubleEndedIterator<Item = &'a Frame<K, V, S, M>> + use<'a, K, V, S, M> {
+        self.front.iter().chain(self.back.iter())
+    }
+}
+
+
+impl<'a, K, V, S, M> ContextView<'a, K, V, S, M>
+where K: Hash + Eq, S: BuildHasher {
+    /// Whether a key is present in the view.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("x", 2)]));
+    ///
+    /// assert!(manager.view(..1).contains_key("x"));
+    /// assert!(!manager.view(..1).contains_key("w"));
+    /// ```
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.search(key).any(|(local, frame)| frame.lookup(key, local).is_some())
+    }
+
+    /// Returns a reference to the value associated with `key`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    /// manager.push(HashMap::from([("w", 3)]));
+    ///
+    /// assert_eq!(manager.view(1..).get("w"), Some(&2));
+    /// assert_eq!(manager.view(1..).get("x"), None);
+    /// ```
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.search(key).find_map(|(local, frame)| frame.lookup(key, local))
+    }
+
+    /// Returns a vector of references to all values associated with `key`, ordered by
+    /// precedence.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use contexts::ContextManager;
+    /// let mut manager = ContextManager::new();
+    ///
+    /// manager.push(HashMap::from([("w", 1)]));
+    /// manager.push(HashMap::from([("w", 2)]));
+    /// manager.push(HashMap::from([("w", 3)]));
+    ///
+    /// assert_eq!(manager.view(..2).get_all("w"), vec![&3, &2]);
+    /// ```
+    pub fn get_all<Q>(&self, key: &Q) -> Vec<&V>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        self.search(key).filter_map(|(local, frame)| frame.lookup(key, local)).collect()
+    }
+
+    /// Returns the frames searched for `key` along with whether each is the local context,
+    /// ordered by precedence and stopping before a frame that removed `key`.
+    fn search<Q>(&self, key: &Q) -> impl Iterator<Item = (bool, &'a Frame<K, V, S, M>)>
+    where K: Borrow<Q>, Q: ?Sized + Hash + Eq {
+        let local = self.local;
+
+        self.frames()
+            .enumerate()
+            .map(move |(index, frame)| (local && index == 0, frame))
+            .take_while(move |(_, frame)| !frame.hides(key))
+    }
+}
+
+
+impl<K, Q, V, S, M> Index<&Q> for ContextView<'_, K, V, S, M>
+where K: Hash + Eq + Borrow<Q>, Q: ?Sized + Hash + Eq, S: BuildHasher {
+    type Output = V;
+
+    /// Returns a reference to the value associated with `key`.
+    ///
+    /// Panics if the view is empty, or `key` is not found in any of its contexts.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+
+impl<K, V, S, M> Clone for ContextView<'_, K, V, S, M> {
+    fn clone(&self) -> Self { *self }
+}
+
+
+impl<K, V, S, M> Copy for ContextView<'_, K, V, S, M> {}